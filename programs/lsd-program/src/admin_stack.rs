@@ -1,5 +1,9 @@
-use crate::{Errors, Stack, StakeManager};
-use anchor_lang::prelude::*;
+use crate::{queue_config, ConfigParam, Errors, Stack, StackFeeAccount, StakeManager};
+use anchor_lang::{prelude::*, solana_program::system_program};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{transfer, Mint, Token, TokenAccount, Transfer},
+};
 
 #[derive(Accounts)]
 pub struct TransferStackAdmin<'info> {
@@ -21,6 +25,40 @@ impl<'info> TransferStackAdmin<'info> {
     }
 }
 
+#[derive(Accounts)]
+pub struct SetStackFeeRecipient<'info> {
+    #[account(
+        mut, 
+        has_one = admin @ Errors::AdminNotMatch
+    )]
+    pub stack: Box<Account<'info, Stack>>,
+
+    pub admin: Signer<'info>,
+}
+
+#[event]
+pub struct EventSetStackFeeRecipient {
+    pub stack: Pubkey,
+    pub old_recipient: Pubkey,
+    pub new_recipient: Pubkey,
+}
+
+impl<'info> SetStackFeeRecipient<'info> {
+    pub fn process(&mut self, stack_fee_recipient: Pubkey) -> Result<()> {
+        let old_recipient = self.stack.stack_fee_recipient;
+        self.stack.stack_fee_recipient = stack_fee_recipient;
+
+        emit!(EventSetStackFeeRecipient {
+            stack: self.stack.key(),
+            old_recipient,
+            new_recipient: stack_fee_recipient
+        });
+        Ok(())
+    }
+}
+
+// default of stake managers initialized later, existing ones change theirs through the
+// timelocked set_platform_stack_fee_commission
 #[derive(Accounts)]
 pub struct SetStackFeeCommission<'info> {
     #[account(
@@ -32,11 +70,114 @@ pub struct SetStackFeeCommission<'info> {
     pub admin: Signer<'info>,
 }
 
+#[event]
+pub struct EventSetStackFeeCommission {
+    pub stack: Pubkey,
+    pub old_commission: u64,
+    pub new_commission: u64,
+}
+
 impl<'info> SetStackFeeCommission<'info> {
     pub fn process(&mut self, stack_fee_commission: u64) -> Result<()> {
+        Stack::check_fee_commission(stack_fee_commission)?;
+
+        let old_commission = self.stack.stack_fee_commission;
         self.stack.stack_fee_commission = stack_fee_commission;
 
-        msg!("SetStackFeeCommission: {}", stack_fee_commission);
+        emit!(EventSetStackFeeCommission {
+            stack: self.stack.key(),
+            old_commission,
+            new_commission: stack_fee_commission
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetMaxPlatformFeeCommission<'info> {
+    #[account(
+        mut, 
+        has_one = admin @ Errors::AdminNotMatch
+    )]
+    pub stack: Box<Account<'info, Stack>>,
+
+    pub admin: Signer<'info>,
+}
+
+#[event]
+pub struct EventSetMaxPlatformFeeCommission {
+    pub stack: Pubkey,
+    pub old_commission: u64,
+    pub new_commission: u64,
+}
+
+impl<'info> SetMaxPlatformFeeCommission<'info> {
+    pub fn process(&mut self, max_platform_fee_commission: u64) -> Result<()> {
+        require_gte!(
+            StakeManager::CAL_BASE,
+            max_platform_fee_commission,
+            Errors::PlatformFeeCommissionOverLimit
+        );
+
+        let old_commission = self.stack.max_platform_fee_commission;
+        self.stack.max_platform_fee_commission = max_platform_fee_commission;
+
+        emit!(EventSetMaxPlatformFeeCommission {
+            stack: self.stack.key(),
+            old_commission,
+            new_commission: max_platform_fee_commission
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetMaxFeeBps<'info> {
+    #[account(
+        mut, 
+        has_one = admin @ Errors::AdminNotMatch
+    )]
+    pub stack: Box<Account<'info, Stack>>,
+
+    pub admin: Signer<'info>,
+}
+
+#[event]
+pub struct EventSetMaxFeeBps {
+    pub stack: Pubkey,
+    pub old_max_deposit_fee_bps: u64,
+    pub new_max_deposit_fee_bps: u64,
+    pub old_max_unstake_fee_bps: u64,
+    pub new_max_unstake_fee_bps: u64,
+}
+
+impl<'info> SetMaxFeeBps<'info> {
+    pub fn process(&mut self, max_deposit_fee_bps: u64, max_unstake_fee_bps: u64) -> Result<()> {
+        require_gte!(
+            StakeManager::BPS_BASE,
+            max_deposit_fee_bps,
+            Errors::DepositFeeOverLimit
+        );
+        require_gte!(
+            StakeManager::BPS_BASE,
+            max_unstake_fee_bps,
+            Errors::UnstakeFeeOverLimit
+        );
+
+        let (old_max_deposit_fee_bps, old_max_unstake_fee_bps) = (
+            self.stack.max_deposit_fee_bps,
+            self.stack.max_unstake_fee_bps,
+        );
+        self.stack.max_deposit_fee_bps = max_deposit_fee_bps;
+        self.stack.max_unstake_fee_bps = max_unstake_fee_bps;
+
+        emit!(EventSetMaxFeeBps {
+            stack: self.stack.key(),
+            old_max_deposit_fee_bps,
+            new_max_deposit_fee_bps: max_deposit_fee_bps,
+            old_max_unstake_fee_bps,
+            new_max_unstake_fee_bps: max_unstake_fee_bps
+        });
         Ok(())
     }
 }
@@ -55,14 +196,70 @@ pub struct SetPlatformStackFeeCommission<'info> {
     pub stack: Box<Account<'info, Stack>>,
 
     pub admin: Signer<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[event]
+pub struct EventSetPlatformStackFeeCommission {
+    pub stake_manager: Pubkey,
+    pub old_commission: u64,
+    pub new_commission: u64,
 }
 
 impl<'info> SetPlatformStackFeeCommission<'info> {
     pub fn process(&mut self, stack_fee_commission: u64) -> Result<()> {
-        self.stake_manager.stack_fee_commission = stack_fee_commission;
+        Stack::check_fee_commission(stack_fee_commission)?;
 
-        msg!("SetPlatformStackFeeCommission: {}", stack_fee_commission);
-        Ok(())
+        queue_config(
+            &mut self.stake_manager,
+            ConfigParam::PlatformStackFeeCommission,
+            stack_fee_commission,
+            self.clock.epoch,
+        )
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetConfigTimelock<'info> {
+    #[account(
+        mut, 
+        has_one = stack @ Errors::StackNotMatch,
+    )]
+    pub stake_manager: Box<Account<'info, StakeManager>>,
+
+    #[account(
+        has_one = admin @ Errors::AdminNotMatch
+    )]
+    pub stack: Box<Account<'info, Stack>>,
+
+    pub admin: Signer<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[event]
+pub struct EventSetConfigTimelock {
+    pub stake_manager: Pubkey,
+    pub old_timelock_epochs: u64,
+    pub new_timelock_epochs: u64,
+}
+
+impl<'info> SetConfigTimelock<'info> {
+    pub fn process(&mut self, config_timelock_epochs: u64) -> Result<()> {
+        require_gte!(
+            StakeManager::MAX_CONFIG_TIMELOCK_EPOCHS,
+            config_timelock_epochs,
+            Errors::ConfigTimelockOverLimit
+        );
+
+        // the new timelock waits out the current one
+        queue_config(
+            &mut self.stake_manager,
+            ConfigParam::ConfigTimelockEpochs,
+            config_timelock_epochs,
+            self.clock.epoch,
+        )
     }
 }
 
@@ -123,3 +320,141 @@ impl<'info> RemoveEntrustedStakeManager<'info> {
         Ok(())
     }
 }
+
+#[derive(Accounts)]
+pub struct ClaimStackFee<'info> {
+    #[account(
+        has_one = admin @ Errors::AdminNotMatch
+    )]
+    pub stack: Box<Account<'info, Stack>>,
+
+    pub admin: Signer<'info>,
+
+    pub lsd_token_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        seeds = [
+            &stack.key().to_bytes(),
+            &lsd_token_mint.key().to_bytes(),
+        ],
+        bump = stack_fee_account.bump,
+    )]
+    pub stack_fee_account: Box<Account<'info, StackFeeAccount>>,
+
+    #[account(
+        mut,
+        associated_token::mint = lsd_token_mint,
+        associated_token::authority = stack_fee_account,
+    )]
+    pub stack_fee_escrow: Box<Account<'info, TokenAccount>>,
+
+    // any destination chosen by the admin
+    #[account(
+        mut,
+        token::mint = lsd_token_mint,
+    )]
+    pub claim_to: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[event]
+pub struct EventClaimStackFee {
+    pub stack: Pubkey,
+    pub lsd_token_mint: Pubkey,
+    pub claim_to: Pubkey,
+    pub claim_amount: u64,
+}
+
+impl<'info> ClaimStackFee<'info> {
+    pub fn process(&mut self, claim_amount: u64) -> Result<()> {
+        require_gt!(claim_amount, 0, Errors::AmountUnmatch);
+        require_gte!(
+            self.stack_fee_escrow.amount,
+            claim_amount,
+            Errors::BalanceNotEnough
+        );
+
+        transfer(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                Transfer {
+                    from: self.stack_fee_escrow.to_account_info(),
+                    to: self.claim_to.to_account_info(),
+                    authority: self.stack_fee_account.to_account_info(),
+                },
+                &[&[
+                    &self.stack.key().to_bytes(),
+                    &self.lsd_token_mint.key().to_bytes(),
+                    &[self.stack_fee_account.bump],
+                ]],
+            ),
+            claim_amount,
+        )?;
+
+        emit!(EventClaimStackFee {
+            stack: self.stack.key(),
+            lsd_token_mint: self.lsd_token_mint.key(),
+            claim_to: self.claim_to.key(),
+            claim_amount
+        });
+        Ok(())
+    }
+}
+
+// stake managers created before the stack fee escrow existed create it here
+#[derive(Accounts)]
+pub struct InitStackFeeEscrow<'info> {
+    #[account(
+        has_one = stack @ Errors::StackNotMatch,
+        has_one = lsd_token_mint @ Errors::MintAccountNotMatch,
+    )]
+    pub stake_manager: Box<Account<'info, StakeManager>>,
+
+    pub stack: Box<Account<'info, Stack>>,
+
+    pub lsd_token_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        init_if_needed,
+        space = 8 + std::mem::size_of::<StackFeeAccount>(),
+        payer = rent_payer,
+        rent_exempt = enforce,
+        seeds = [
+            &stack.key().to_bytes(),
+            &lsd_token_mint.key().to_bytes(),
+        ],
+        bump,
+    )]
+    pub stack_fee_account: Box<Account<'info, StackFeeAccount>>,
+
+    #[account(
+        init_if_needed,
+        payer = rent_payer,
+        associated_token::mint = lsd_token_mint,
+        associated_token::authority = stack_fee_account,
+    )]
+    pub stack_fee_escrow: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        owner = system_program::ID
+    )]
+    pub rent_payer: Signer<'info>,
+
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitStackFeeEscrow<'info> {
+    pub fn process(&mut self, stack_fee_account_bump: u8) -> Result<()> {
+        self.stack_fee_account.bump = stack_fee_account_bump;
+
+        msg!(
+            "InitStackFeeEscrow: {}",
+            self.stack_fee_escrow.key().to_string()
+        );
+        Ok(())
+    }
+}
@@ -1,5 +1,138 @@
-use crate::{Errors, StakeManager};
-use anchor_lang::{prelude::*, system_program};
+use crate::{
+    ConfigParam, EraRate, Errors, EventSetConfigTimelock, EventSetPlatformStackFeeCommission,
+    PendingConfig, Role, Stack, StakeManager, StakeManagerV0, UnstakeAccount, UnstakeAccountV0,
+};
+use anchor_lang::{
+    prelude::*,
+    system_program::{self, transfer, Transfer},
+    Discriminator,
+};
+
+#[event]
+pub struct EventQueueConfig {
+    pub stake_manager: Pubkey,
+    pub param: ConfigParam,
+    pub old_value: u64,
+    pub new_value: u64,
+    pub apply_epoch: u64,
+}
+
+#[event]
+pub struct EventCancelConfig {
+    pub stake_manager: Pubkey,
+    pub param: ConfigParam,
+    pub value: u64,
+}
+
+// queue the change until the timelock passes, apply it at once without timelock
+pub fn queue_config(
+    stake_manager: &mut Account<StakeManager>,
+    param: ConfigParam,
+    value: u64,
+    epoch: u64,
+) -> Result<()> {
+    if stake_manager.config_timelock_epochs == 0 {
+        apply_config(stake_manager, param, value);
+        return Ok(());
+    }
+
+    let apply_epoch = epoch + stake_manager.config_timelock_epochs;
+    stake_manager.pending_configs.retain(|e| e.param != param);
+    stake_manager.pending_configs.push(PendingConfig {
+        param,
+        value,
+        apply_epoch,
+    });
+
+    emit!(EventQueueConfig {
+        stake_manager: stake_manager.key(),
+        param,
+        old_value: stake_manager.config_value(param),
+        new_value: value,
+        apply_epoch
+    });
+    Ok(())
+}
+
+pub fn apply_config(stake_manager: &mut Account<StakeManager>, param: ConfigParam, value: u64) {
+    let key = stake_manager.key();
+    let old_value = stake_manager.config_value(param);
+    stake_manager.set_config_value(param, value);
+
+    match param {
+        ConfigParam::PlatformFeeCommission => emit!(EventSetPlatformFeeCommission {
+            stake_manager: key,
+            old_commission: old_value,
+            new_commission: value
+        }),
+        ConfigParam::DepositFeeBps => emit!(EventSetDepositFeeBps {
+            stake_manager: key,
+            old_fee_bps: old_value,
+            new_fee_bps: value
+        }),
+        ConfigParam::UnstakeFeeBps => emit!(EventSetUnstakeFeeBps {
+            stake_manager: key,
+            old_fee_bps: old_value,
+            new_fee_bps: value
+        }),
+        ConfigParam::ReferralFeeCommission => emit!(EventSetReferralFeeCommission {
+            stake_manager: key,
+            old_commission: old_value,
+            new_commission: value
+        }),
+        ConfigParam::RateChangeLimit => emit!(EventSetRateChangeLimit {
+            stake_manager: key,
+            old_rate_change_limit: old_value,
+            new_rate_change_limit: value
+        }),
+        ConfigParam::UnbondingDuration => emit!(EventSetUnbondingDuration {
+            stake_manager: key,
+            old_duration: old_value,
+            new_duration: value
+        }),
+        ConfigParam::InsuranceFeeCommission => emit!(EventSetInsuranceFeeCommission {
+            stake_manager: key,
+            old_commission: old_value,
+            new_commission: value
+        }),
+        ConfigParam::RateUpLimit => emit!(EventSetRateUpLimit {
+            stake_manager: key,
+            old_limit: old_value,
+            new_limit: value
+        }),
+        ConfigParam::RateDownLimit => emit!(EventSetRateDownLimit {
+            stake_manager: key,
+            old_limit: old_value,
+            new_limit: value
+        }),
+        ConfigParam::CrankFeeCommission => emit!(EventSetCrankFeeCommission {
+            stake_manager: key,
+            old_commission: old_value,
+            new_commission: value
+        }),
+        ConfigParam::MinStakeAmount => emit!(EventSetMinStakeAmount {
+            stake_manager: key,
+            old_amount: old_value,
+            new_amount: value
+        }),
+        ConfigParam::PlatformStackFeeCommission => emit!(EventSetPlatformStackFeeCommission {
+            stake_manager: key,
+            old_commission: old_value,
+            new_commission: value
+        }),
+        ConfigParam::ConfigTimelockEpochs => emit!(EventSetConfigTimelock {
+            stake_manager: key,
+            old_timelock_epochs: old_value,
+            new_timelock_epochs: value
+        }),
+        ConfigParam::CrankReward => emit!(EventSetCrankReward {
+            stake_manager: key,
+            old_reward: old_value,
+            new_reward: value
+        }),
+    }
+}
+
 #[derive(Accounts)]
 pub struct TransferStakeManagerAdmin<'info> {
     #[account(
@@ -41,7 +174,70 @@ impl<'info> TransferBalancer<'info> {
 }
 
 #[derive(Accounts)]
-pub struct SetMinStakeAmount<'info> {
+pub struct SetRole<'info> {
+    #[account(
+        mut, 
+        has_one = admin @ Errors::AdminNotMatch
+    )]
+    pub stake_manager: Box<Account<'info, StakeManager>>,
+
+    pub admin: Signer<'info>,
+}
+
+#[event]
+pub struct EventSetRole {
+    pub stake_manager: Pubkey,
+    pub role: Role,
+    pub old_key: Pubkey,
+    pub new_key: Pubkey,
+}
+
+impl<'info> SetRole<'info> {
+    pub fn process(&mut self, role: Role, new_key: Pubkey) -> Result<()> {
+        let old_key = self.stake_manager.role(role);
+        self.stake_manager.set_role(role, new_key);
+
+        emit!(EventSetRole {
+            stake_manager: self.stake_manager.key(),
+            role,
+            old_key,
+            new_key
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    #[account(
+        mut, 
+        constraint = stake_manager.has_role(Role::Pauser, pauser.key) @ Errors::RoleNotMatch
+    )]
+    pub stake_manager: Box<Account<'info, StakeManager>>,
+
+    pub pauser: Signer<'info>,
+}
+
+#[event]
+pub struct EventSetPaused {
+    pub stake_manager: Pubkey,
+    pub paused: bool,
+}
+
+impl<'info> SetPaused<'info> {
+    pub fn process(&mut self, paused: bool) -> Result<()> {
+        self.stake_manager.paused = paused;
+
+        emit!(EventSetPaused {
+            stake_manager: self.stake_manager.key(),
+            paused
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetDepositAuthority<'info> {
     #[account(
         mut, 
         has_one = admin @ Errors::AdminNotMatch
@@ -51,11 +247,96 @@ pub struct SetMinStakeAmount<'info> {
     pub admin: Signer<'info>,
 }
 
+#[event]
+pub struct EventSetDepositAuthority {
+    pub stake_manager: Pubkey,
+    pub old_deposit_authority: Option<Pubkey>,
+    pub new_deposit_authority: Option<Pubkey>,
+}
+
+impl<'info> SetDepositAuthority<'info> {
+    pub fn process(&mut self, deposit_authority: Option<Pubkey>) -> Result<()> {
+        let old_deposit_authority = self.stake_manager.deposit_authority;
+        self.stake_manager.deposit_authority = deposit_authority;
+
+        emit!(EventSetDepositAuthority {
+            stake_manager: self.stake_manager.key(),
+            old_deposit_authority,
+            new_deposit_authority: deposit_authority
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetMinStakeAmount<'info> {
+    #[account(
+        mut, 
+        constraint = stake_manager.has_role(Role::ParamManager, authority.key) @ Errors::RoleNotMatch
+    )]
+    pub stake_manager: Box<Account<'info, StakeManager>>,
+
+    pub authority: Signer<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[event]
+pub struct EventSetMinStakeAmount {
+    pub stake_manager: Pubkey,
+    pub old_amount: u64,
+    pub new_amount: u64,
+}
+
 impl<'info> SetMinStakeAmount<'info> {
     pub fn process(&mut self, amount: u64) -> Result<()> {
-        self.stake_manager.min_stake_amount = amount;
+        StakeManager::check_min_stake_amount(amount)?;
+
+        queue_config(
+            &mut self.stake_manager,
+            ConfigParam::MinStakeAmount,
+            amount,
+            self.clock.epoch,
+        )
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetDepositCaps<'info> {
+    #[account(
+        mut, 
+        constraint = stake_manager.has_role(Role::ParamManager, authority.key) @ Errors::RoleNotMatch
+    )]
+    pub stake_manager: Box<Account<'info, StakeManager>>,
+
+    pub authority: Signer<'info>,
+}
 
-        msg!("SetMinStakeAmount: amount: {}", amount);
+#[event]
+pub struct EventSetDepositCaps {
+    pub stake_manager: Pubkey,
+    pub old_max_active: u64,
+    pub new_max_active: u64,
+    pub old_epoch_deposit_cap: u64,
+    pub new_epoch_deposit_cap: u64,
+}
+
+impl<'info> SetDepositCaps<'info> {
+    pub fn process(&mut self, max_active: u64, epoch_deposit_cap: u64) -> Result<()> {
+        let (old_max_active, old_epoch_deposit_cap) = (
+            self.stake_manager.max_active,
+            self.stake_manager.epoch_deposit_cap,
+        );
+        self.stake_manager.max_active = max_active;
+        self.stake_manager.epoch_deposit_cap = epoch_deposit_cap;
+
+        emit!(EventSetDepositCaps {
+            stake_manager: self.stake_manager.key(),
+            old_max_active,
+            new_max_active: max_active,
+            old_epoch_deposit_cap,
+            new_epoch_deposit_cap: epoch_deposit_cap
+        });
         Ok(())
     }
 }
@@ -64,19 +345,32 @@ impl<'info> SetMinStakeAmount<'info> {
 pub struct SetUnbondingDuration<'info> {
     #[account(
         mut, 
-        has_one = admin @ Errors::AdminNotMatch
+        constraint = stake_manager.has_role(Role::ParamManager, authority.key) @ Errors::RoleNotMatch
     )]
     pub stake_manager: Box<Account<'info, StakeManager>>,
 
-    pub admin: Signer<'info>,
+    pub authority: Signer<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[event]
+pub struct EventSetUnbondingDuration {
+    pub stake_manager: Pubkey,
+    pub old_duration: u64,
+    pub new_duration: u64,
 }
 
 impl<'info> SetUnbondingDuration<'info> {
     pub fn process(&mut self, duration: u64) -> Result<()> {
-        self.stake_manager.unbonding_duration = duration;
+        StakeManager::check_unbonding_duration(duration)?;
 
-        msg!("SetUnbondingDuration: duration: {}", duration);
-        Ok(())
+        queue_config(
+            &mut self.stake_manager,
+            ConfigParam::UnbondingDuration,
+            duration,
+            self.clock.epoch,
+        )
     }
 }
 
@@ -84,24 +378,40 @@ impl<'info> SetUnbondingDuration<'info> {
 pub struct SetPlatformFeeCommission<'info> {
     #[account(
         mut, 
-        has_one = admin @ Errors::AdminNotMatch
+        constraint = stake_manager.has_role(Role::FeeManager, authority.key) @ Errors::RoleNotMatch,
+        has_one = stack @ Errors::StackNotMatch,
     )]
     pub stake_manager: Box<Account<'info, StakeManager>>,
 
-    pub admin: Signer<'info>,
+    pub stack: Box<Account<'info, Stack>>,
+
+    pub authority: Signer<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[event]
+pub struct EventSetPlatformFeeCommission {
+    pub stake_manager: Pubkey,
+    pub old_commission: u64,
+    pub new_commission: u64,
 }
 
 impl<'info> SetPlatformFeeCommission<'info> {
     pub fn process(&mut self, platform_fee_commission: u64) -> Result<()> {
-        self.stake_manager.platform_fee_commission = platform_fee_commission;
+        StakeManager::check_platform_fee_commission(&self.stack, platform_fee_commission)?;
 
-        msg!("SetPlatformFeeCommission: {}", platform_fee_commission);
-        Ok(())
+        queue_config(
+            &mut self.stake_manager,
+            ConfigParam::PlatformFeeCommission,
+            platform_fee_commission,
+            self.clock.epoch,
+        )
     }
 }
 
 #[derive(Accounts)]
-pub struct SetRateChangeLimit<'info> {
+pub struct SetPlatformFeeRecipient<'info> {
     #[account(
         mut, 
         has_one = admin @ Errors::AdminNotMatch
@@ -111,20 +421,368 @@ pub struct SetRateChangeLimit<'info> {
     pub admin: Signer<'info>,
 }
 
-impl<'info> SetRateChangeLimit<'info> {
-    pub fn process(&mut self, rate_chagne_limit: u64) -> Result<()> {
-        self.stake_manager.rate_change_limit = rate_chagne_limit;
+#[event]
+pub struct EventSetPlatformFeeRecipient {
+    pub stake_manager: Pubkey,
+    pub old_recipient: Pubkey,
+    pub new_recipient: Pubkey,
+}
 
-        msg!(
-            "SetRateChangeLimit: rate change limit: {}",
-            rate_chagne_limit
+impl<'info> SetPlatformFeeRecipient<'info> {
+    pub fn process(&mut self, platform_fee_recipient: Pubkey) -> Result<()> {
+        let old_recipient = self.stake_manager.platform_fee_recipient;
+        self.stake_manager.platform_fee_recipient = platform_fee_recipient;
+
+        emit!(EventSetPlatformFeeRecipient {
+            stake_manager: self.stake_manager.key(),
+            old_recipient,
+            new_recipient: platform_fee_recipient
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetDepositFeeBps<'info> {
+    #[account(
+        mut, 
+        constraint = stake_manager.has_role(Role::FeeManager, authority.key) @ Errors::RoleNotMatch,
+        has_one = stack @ Errors::StackNotMatch,
+    )]
+    pub stake_manager: Box<Account<'info, StakeManager>>,
+
+    pub stack: Box<Account<'info, Stack>>,
+
+    pub authority: Signer<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[event]
+pub struct EventSetDepositFeeBps {
+    pub stake_manager: Pubkey,
+    pub old_fee_bps: u64,
+    pub new_fee_bps: u64,
+}
+
+impl<'info> SetDepositFeeBps<'info> {
+    pub fn process(&mut self, deposit_fee_bps: u64) -> Result<()> {
+        require_gte!(
+            self.stack.max_deposit_fee_bps,
+            deposit_fee_bps,
+            Errors::DepositFeeOverLimit
+        );
+
+        queue_config(
+            &mut self.stake_manager,
+            ConfigParam::DepositFeeBps,
+            deposit_fee_bps,
+            self.clock.epoch,
+        )
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetUnstakeFeeBps<'info> {
+    #[account(
+        mut, 
+        constraint = stake_manager.has_role(Role::FeeManager, authority.key) @ Errors::RoleNotMatch,
+        has_one = stack @ Errors::StackNotMatch,
+    )]
+    pub stake_manager: Box<Account<'info, StakeManager>>,
+
+    pub stack: Box<Account<'info, Stack>>,
+
+    pub authority: Signer<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[event]
+pub struct EventSetUnstakeFeeBps {
+    pub stake_manager: Pubkey,
+    pub old_fee_bps: u64,
+    pub new_fee_bps: u64,
+}
+
+impl<'info> SetUnstakeFeeBps<'info> {
+    pub fn process(&mut self, unstake_fee_bps: u64) -> Result<()> {
+        require_gte!(
+            self.stack.max_unstake_fee_bps,
+            unstake_fee_bps,
+            Errors::UnstakeFeeOverLimit
+        );
+
+        queue_config(
+            &mut self.stake_manager,
+            ConfigParam::UnstakeFeeBps,
+            unstake_fee_bps,
+            self.clock.epoch,
+        )
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetReferralFeeCommission<'info> {
+    #[account(
+        mut, 
+        constraint = stake_manager.has_role(Role::FeeManager, authority.key) @ Errors::RoleNotMatch
+    )]
+    pub stake_manager: Box<Account<'info, StakeManager>>,
+
+    pub authority: Signer<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[event]
+pub struct EventSetReferralFeeCommission {
+    pub stake_manager: Pubkey,
+    pub old_commission: u64,
+    pub new_commission: u64,
+}
+
+impl<'info> SetReferralFeeCommission<'info> {
+    pub fn process(&mut self, referral_fee_commission: u64) -> Result<()> {
+        require_gte!(
+            StakeManager::CAL_BASE,
+            referral_fee_commission,
+            Errors::ReferralFeeCommissionOverLimit
+        );
+
+        queue_config(
+            &mut self.stake_manager,
+            ConfigParam::ReferralFeeCommission,
+            referral_fee_commission,
+            self.clock.epoch,
+        )
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetInsuranceFeeCommission<'info> {
+    #[account(
+        mut, 
+        constraint = stake_manager.has_role(Role::FeeManager, authority.key) @ Errors::RoleNotMatch
+    )]
+    pub stake_manager: Box<Account<'info, StakeManager>>,
+
+    pub authority: Signer<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[event]
+pub struct EventSetInsuranceFeeCommission {
+    pub stake_manager: Pubkey,
+    pub old_commission: u64,
+    pub new_commission: u64,
+}
+
+impl<'info> SetInsuranceFeeCommission<'info> {
+    pub fn process(&mut self, insurance_fee_commission: u64) -> Result<()> {
+        require_gte!(
+            StakeManager::CAL_BASE,
+            insurance_fee_commission,
+            Errors::InsuranceFeeCommissionOverLimit
         );
+
+        queue_config(
+            &mut self.stake_manager,
+            ConfigParam::InsuranceFeeCommission,
+            insurance_fee_commission,
+            self.clock.epoch,
+        )
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetCrankFeeCommission<'info> {
+    #[account(
+        mut, 
+        constraint = stake_manager.has_role(Role::FeeManager, authority.key) @ Errors::RoleNotMatch
+    )]
+    pub stake_manager: Box<Account<'info, StakeManager>>,
+
+    pub authority: Signer<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[event]
+pub struct EventSetCrankFeeCommission {
+    pub stake_manager: Pubkey,
+    pub old_commission: u64,
+    pub new_commission: u64,
+}
+
+impl<'info> SetCrankFeeCommission<'info> {
+    pub fn process(&mut self, crank_fee_commission: u64) -> Result<()> {
+        require_gte!(
+            StakeManager::CAL_BASE,
+            crank_fee_commission,
+            Errors::CrankFeeCommissionOverLimit
+        );
+
+        queue_config(
+            &mut self.stake_manager,
+            ConfigParam::CrankFeeCommission,
+            crank_fee_commission,
+            self.clock.epoch,
+        )
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetCrankReward<'info> {
+    #[account(
+        mut, 
+        constraint = stake_manager.has_role(Role::ParamManager, authority.key) @ Errors::RoleNotMatch
+    )]
+    pub stake_manager: Box<Account<'info, StakeManager>>,
+
+    pub authority: Signer<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[event]
+pub struct EventSetCrankReward {
+    pub stake_manager: Pubkey,
+    pub old_reward: u64,
+    pub new_reward: u64,
+}
+
+impl<'info> SetCrankReward<'info> {
+    pub fn process(&mut self, crank_reward: u64) -> Result<()> {
+        StakeManager::check_crank_reward(crank_reward)?;
+
+        queue_config(
+            &mut self.stake_manager,
+            ConfigParam::CrankReward,
+            crank_reward,
+            self.clock.epoch,
+        )
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetRentAsYield<'info> {
+    #[account(
+        mut, 
+        constraint = stake_manager.has_role(Role::ParamManager, authority.key) @ Errors::RoleNotMatch
+    )]
+    pub stake_manager: Box<Account<'info, StakeManager>>,
+
+    pub authority: Signer<'info>,
+}
+
+#[event]
+pub struct EventSetRentAsYield {
+    pub stake_manager: Pubkey,
+    pub rent_as_yield: bool,
+}
+
+impl<'info> SetRentAsYield<'info> {
+    pub fn process(&mut self, rent_as_yield: bool) -> Result<()> {
+        // only affects stake accounts created afterwards
+        self.stake_manager.rent_as_yield = rent_as_yield;
+
+        emit!(EventSetRentAsYield {
+            stake_manager: self.stake_manager.key(),
+            rent_as_yield
+        });
         Ok(())
     }
 }
 
 #[derive(Accounts)]
-pub struct AddValidator<'info> {
+pub struct SetRateChangeLimit<'info> {
+    #[account(
+        mut, 
+        constraint = stake_manager.has_role(Role::ParamManager, authority.key) @ Errors::RoleNotMatch
+    )]
+    pub stake_manager: Box<Account<'info, StakeManager>>,
+
+    pub authority: Signer<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[event]
+pub struct EventSetRateChangeLimit {
+    pub stake_manager: Pubkey,
+    pub old_rate_change_limit: u64,
+    pub new_rate_change_limit: u64,
+}
+
+impl<'info> SetRateChangeLimit<'info> {
+    pub fn process(&mut self, rate_chagne_limit: u64) -> Result<()> {
+        StakeManager::check_rate_change_limit(rate_chagne_limit)?;
+
+        queue_config(
+            &mut self.stake_manager,
+            ConfigParam::RateChangeLimit,
+            rate_chagne_limit,
+            self.clock.epoch,
+        )
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetRateDirectionLimits<'info> {
+    #[account(
+        mut, 
+        constraint = stake_manager.has_role(Role::ParamManager, authority.key) @ Errors::RoleNotMatch
+    )]
+    pub stake_manager: Box<Account<'info, StakeManager>>,
+
+    pub authority: Signer<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+}
+
+#[event]
+pub struct EventSetRateUpLimit {
+    pub stake_manager: Pubkey,
+    pub old_limit: u64,
+    pub new_limit: u64,
+}
+
+#[event]
+pub struct EventSetRateDownLimit {
+    pub stake_manager: Pubkey,
+    pub old_limit: u64,
+    pub new_limit: u64,
+}
+
+impl<'info> SetRateDirectionLimits<'info> {
+    // 0 falls back to rate_change_limit
+    pub fn process(&mut self, rate_up_limit: u64, rate_down_limit: u64) -> Result<()> {
+        if rate_up_limit > 0 {
+            StakeManager::check_rate_change_limit(rate_up_limit)?;
+        }
+        if rate_down_limit > 0 {
+            StakeManager::check_rate_change_limit(rate_down_limit)?;
+        }
+
+        queue_config(
+            &mut self.stake_manager,
+            ConfigParam::RateUpLimit,
+            rate_up_limit,
+            self.clock.epoch,
+        )?;
+        queue_config(
+            &mut self.stake_manager,
+            ConfigParam::RateDownLimit,
+            rate_down_limit,
+            self.clock.epoch,
+        )
+    }
+}
+
+#[derive(Accounts)]
+pub struct CancelPendingConfig<'info> {
     #[account(
         mut, 
         has_one = admin @ Errors::AdminNotMatch
@@ -134,6 +792,40 @@ pub struct AddValidator<'info> {
     pub admin: Signer<'info>,
 }
 
+impl<'info> CancelPendingConfig<'info> {
+    pub fn process(&mut self, param: ConfigParam) -> Result<()> {
+        let pending_config = self
+            .stake_manager
+            .pending_configs
+            .iter()
+            .find(|e| e.param == param)
+            .cloned()
+            .ok_or_else(|| error!(Errors::PendingConfigNotExist))?;
+
+        self.stake_manager
+            .pending_configs
+            .retain(|e| e.param != param);
+
+        emit!(EventCancelConfig {
+            stake_manager: self.stake_manager.key(),
+            param,
+            value: pending_config.value
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct AddValidator<'info> {
+    #[account(
+        mut, 
+        constraint = stake_manager.has_role(Role::ValidatorManager, authority.key) @ Errors::RoleNotMatch
+    )]
+    pub stake_manager: Box<Account<'info, StakeManager>>,
+
+    pub authority: Signer<'info>,
+}
+
 impl<'info> AddValidator<'info> {
     pub fn process(&mut self, new_validator: Pubkey) -> Result<()> {
         require!(
@@ -155,11 +847,11 @@ impl<'info> AddValidator<'info> {
 pub struct RemoveValidator<'info> {
     #[account(
         mut, 
-        has_one = admin @ Errors::AdminNotMatch
+        constraint = stake_manager.has_role(Role::ValidatorManager, authority.key) @ Errors::RoleNotMatch
     )]
     pub stake_manager: Box<Account<'info, StakeManager>>,
 
-    pub admin: Signer<'info>,
+    pub authority: Signer<'info>,
 }
 
 impl<'info> RemoveValidator<'info> {
@@ -210,3 +902,335 @@ impl<'info> ReallocStakeManager<'info> {
         Ok(())
     }
 }
+
+#[derive(Accounts)]
+pub struct MigrateStakeManager<'info> {
+    /// CHECK: older layout, checked and converted in process
+    #[account(
+        mut,
+        owner = crate::ID
+    )]
+    pub stake_manager: UncheckedAccount<'info>,
+
+    /// CHECK: pool of the stake manager, checked against the stored bump in process
+    pub stake_pool: UncheckedAccount<'info>,
+
+    pub admin: Signer<'info>,
+
+    #[account(
+        mut,
+        owner = system_program::ID,
+    )]
+    pub rent_payer: Signer<'info>,
+
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct EventMigrateStakeManager {
+    pub stake_manager: Pubkey,
+    pub version: u8,
+    pub new_size: u32,
+    pub tracked_pool_balance: u64,
+    pub unstake_accounts: Vec<Pubkey>,
+    pub total_unstake_pending: u64,
+}
+
+impl<'info> MigrateStakeManager<'info> {
+    // reallocs an account of the older layout and seeds the appended fields, no other
+    // instruction of the stake manager runs before this
+    // remaining accounts: outstanding unstake accounts of the older layout, they are converted
+    // and queued so the pool keeps their lamports out of bonding
+    pub fn process(
+        &mut self,
+        new_size: u32,
+        remaining_accounts: &'info [AccountInfo<'info>],
+    ) -> Result<()> {
+        let old = {
+            let data = self.stake_manager.try_borrow_data()?;
+            require!(
+                data.len() >= 8 && data[..8] == StakeManager::DISCRIMINATOR,
+                ErrorCode::AccountDiscriminatorMismatch
+            );
+            if let Ok(stake_manager) = StakeManager::try_deserialize(&mut &data[..]) {
+                require_neq!(
+                    stake_manager.version,
+                    StakeManager::CURRENT_VERSION,
+                    Errors::StakeManagerAlreadyMigrated
+                );
+            }
+            StakeManagerV0::deserialize(&mut &data[8..])?
+        };
+        require_keys_eq!(old.admin, self.admin.key(), Errors::AdminNotMatch);
+
+        let stake_pool = Pubkey::create_program_address(
+            &[
+                &self.stake_manager.key().to_bytes(),
+                StakeManager::POOL_SEED,
+                &[old.pool_seed_bump],
+            ],
+            &crate::ID,
+        )
+        .map_err(|_| error!(ErrorCode::ConstraintSeeds))?;
+        require_keys_eq!(
+            stake_pool,
+            self.stake_pool.key(),
+            ErrorCode::ConstraintSeeds
+        );
+
+        // lamports already in the pool are accounted for, only later transfers are excess
+        let tracked_pool_balance = self
+            .stake_pool
+            .lamports()
+            .saturating_sub(old.rent_exempt_for_pool_acc);
+
+        let mut stake_manager = StakeManager {
+            admin: old.admin,
+            balancer: old.balancer,
+            stack: old.stack,
+            lsd_token_mint: old.lsd_token_mint,
+            pool_seed_bump: old.pool_seed_bump,
+            rent_exempt_for_pool_acc: old.rent_exempt_for_pool_acc,
+            min_stake_amount: old.min_stake_amount,
+            platform_fee_commission: old.platform_fee_commission,
+            stack_fee_commission: old.stack_fee_commission,
+            rate_change_limit: old.rate_change_limit,
+            stake_accounts_len_limit: old.stake_accounts_len_limit,
+            split_accounts_len_limit: old.split_accounts_len_limit,
+            unbonding_duration: old.unbonding_duration,
+            latest_era: old.latest_era,
+            rate: old.rate,
+            era_bond: old.era_bond,
+            era_unbond: old.era_unbond,
+            active: old.active,
+            total_platform_fee: old.total_platform_fee,
+            validators: old.validators,
+            stake_accounts: old.stake_accounts,
+            split_accounts: old.split_accounts,
+            era_rates: old
+                .era_rates
+                .iter()
+                .map(|e| EraRate {
+                    era: e.era,
+                    rate: e.rate,
+                    era_epochs: 1,
+                })
+                .collect(),
+            era_process_data: old.era_process_data,
+            unstake_requested: 0,
+            unstake_fulfilled: 0,
+            unstake_claimed: 0,
+            referral_fee_commission: 0,
+            referral_active: 0,
+            referral_fee_per_active: 0,
+            platform_fee_recipient: old.admin,
+            deposit_fee_bps: 0,
+            unstake_fee_bps: 0,
+            pending_fee: 0,
+            config_timelock_epochs: StakeManager::DEFAULT_CONFIG_TIMELOCK_EPOCHS,
+            pending_configs: vec![],
+            fee_manager: old.admin,
+            validator_manager: old.admin,
+            pauser: old.admin,
+            param_manager: old.admin,
+            paused: false,
+            deposit_authority: None,
+            max_active: 0,
+            epoch_deposit_cap: 0,
+            epoch_deposit_epoch: 0,
+            epoch_deposited: 0,
+            insurance_fee_commission: 0,
+            insurance_buffer: 0,
+            rate_up_limit: 0,
+            rate_down_limit: 0,
+            tracked_pool_balance,
+            excess_reward: 0,
+            total_unstake_pending: 0,
+            total_unstake_claimable: 0,
+            unbonding_splits: vec![],
+            crank_fee_commission: 0,
+            crank_reward: 0,
+            crank_budget: 0,
+            rent_as_yield: false,
+            rent_payers: vec![],
+            era_epochs: 1,
+            version: StakeManager::CURRENT_VERSION,
+        };
+
+        let mut unstake_accounts = Vec::with_capacity(remaining_accounts.len());
+        for account_info in remaining_accounts.iter() {
+            migrate_unstake_account(
+                &mut stake_manager,
+                self.stake_manager.key(),
+                account_info,
+                &self.rent_payer,
+                &self.system_program,
+                &self.rent,
+            )?;
+            unstake_accounts.push(account_info.key());
+        }
+
+        let account_info = self.stake_manager.to_account_info();
+        if new_size as usize > account_info.data_len() {
+            let rent_needed = self
+                .rent
+                .minimum_balance(new_size as usize)
+                .saturating_sub(account_info.lamports());
+            if rent_needed > 0 {
+                transfer(
+                    CpiContext::new(
+                        self.system_program.to_account_info(),
+                        Transfer {
+                            from: self.rent_payer.to_account_info(),
+                            to: account_info.clone(),
+                        },
+                    ),
+                    rent_needed,
+                )?;
+            }
+            account_info.realloc(new_size as usize, false)?;
+        }
+
+        // fails when new_size can not hold the new layout
+        let mut data = account_info.try_borrow_mut_data()?;
+        stake_manager.try_serialize(&mut &mut data[..])?;
+
+        emit!(EventMigrateStakeManager {
+            stake_manager: self.stake_manager.key(),
+            version: StakeManager::CURRENT_VERSION,
+            new_size,
+            tracked_pool_balance,
+            unstake_accounts,
+            total_unstake_pending: stake_manager.total_unstake_pending
+        });
+        Ok(())
+    }
+}
+
+// converts an unstake account of the layout before the unstake queue and queues its amount,
+// its rent goes back to the recipient since the original payer was never recorded
+fn migrate_unstake_account<'info>(
+    stake_manager: &mut StakeManager,
+    stake_manager_key: Pubkey,
+    unstake_account: &AccountInfo<'info>,
+    rent_payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    rent: &Rent,
+) -> Result<UnstakeAccount> {
+    require_keys_eq!(
+        *unstake_account.owner,
+        crate::ID,
+        ErrorCode::ConstraintOwner
+    );
+    require!(unstake_account.is_writable, ErrorCode::ConstraintMut);
+
+    let old = {
+        let data = unstake_account.try_borrow_data()?;
+        require!(
+            data.len() >= 8 && data[..8] == UnstakeAccount::DISCRIMINATOR,
+            ErrorCode::AccountDiscriminatorMismatch
+        );
+        require_eq!(
+            data.len(),
+            UnstakeAccountV0::LEN,
+            Errors::UnstakeAccountAlreadyMigrated
+        );
+        UnstakeAccountV0::deserialize(&mut &data[8..])?
+    };
+    require_keys_eq!(
+        old.stake_manager,
+        stake_manager_key,
+        Errors::InvalidUnstakeAccount
+    );
+
+    // the unstake was unbonded by the era after its creation, like unstake_funding_era
+    let queue_position = stake_manager.add_unstake(old.amount);
+    let new = UnstakeAccount {
+        stake_manager: old.stake_manager,
+        recipient: old.recipient,
+        amount: old.amount,
+        created_epoch: old.created_epoch,
+        queue_position,
+        rent_payer: old.recipient,
+        era: old.created_epoch + 1,
+    };
+
+    let new_size = 8 + std::mem::size_of::<UnstakeAccount>();
+    let rent_needed = rent
+        .minimum_balance(new_size)
+        .saturating_sub(unstake_account.lamports());
+    if rent_needed > 0 {
+        transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                Transfer {
+                    from: rent_payer.to_account_info(),
+                    to: unstake_account.clone(),
+                },
+            ),
+            rent_needed,
+        )?;
+    }
+    unstake_account.realloc(new_size, false)?;
+
+    let mut data = unstake_account.try_borrow_mut_data()?;
+    new.try_serialize(&mut &mut data[..])?;
+    Ok(new)
+}
+
+#[derive(Accounts)]
+pub struct MigrateUnstakeAccount<'info> {
+    #[account(mut)]
+    pub stake_manager: Box<Account<'info, StakeManager>>,
+
+    /// CHECK: older layout, checked and converted in process
+    #[account(mut)]
+    pub unstake_account: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        owner = system_program::ID,
+    )]
+    pub rent_payer: Signer<'info>,
+
+    pub rent: Sysvar<'info, Rent>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct EventMigrateUnstakeAccount {
+    pub stake_manager: Pubkey,
+    pub unstake_account: Pubkey,
+    pub amount: u64,
+    pub queue_position: u64,
+}
+
+impl<'info> MigrateUnstakeAccount<'info> {
+    // unstake accounts left out of migrate_stake_manager, anyone may convert them
+    pub fn process(&mut self) -> Result<()> {
+        require_eq!(
+            self.stake_manager.version,
+            StakeManager::CURRENT_VERSION,
+            Errors::StakeManagerNotMigrated
+        );
+
+        let stake_manager_key = self.stake_manager.key();
+        let unstake_account = migrate_unstake_account(
+            &mut self.stake_manager,
+            stake_manager_key,
+            &self.unstake_account,
+            &self.rent_payer,
+            &self.system_program,
+            &self.rent,
+        )?;
+
+        emit!(EventMigrateUnstakeAccount {
+            stake_manager: stake_manager_key,
+            unstake_account: self.unstake_account.key(),
+            amount: unstake_account.amount,
+            queue_position: unstake_account.queue_position
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,39 @@
+use crate::{apply_config, Errors, Stack, StakeManager};
+use anchor_lang::prelude::*;
+
+#[derive(Accounts)]
+pub struct ApplyPendingConfig<'info> {
+    #[account(
+        mut,
+        has_one = stack @ Errors::StackNotMatch,
+    )]
+    pub stake_manager: Box<Account<'info, StakeManager>>,
+
+    pub stack: Box<Account<'info, Stack>>,
+
+    pub clock: Sysvar<'info, Clock>,
+}
+
+impl<'info> ApplyPendingConfig<'info> {
+    pub fn process(&mut self) -> Result<()> {
+        let epoch = self.clock.epoch;
+        let (ready_configs, pending_configs) = self
+            .stake_manager
+            .pending_configs
+            .iter()
+            .cloned()
+            .partition::<Vec<_>, _>(|e| e.apply_epoch <= epoch);
+
+        require!(!ready_configs.is_empty(), Errors::PendingConfigNotReady);
+        for config in ready_configs.iter() {
+            StakeManager::check_stack_cap(&self.stack, config.param, config.value)?;
+        }
+
+        self.stake_manager.pending_configs = pending_configs;
+        for config in ready_configs {
+            apply_config(&mut self.stake_manager, config.param, config.value);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,140 @@
+use crate::{CrankerAccount, Errors, StakeManager};
+use anchor_lang::{prelude::*, solana_program::system_program};
+use anchor_spl::token::{transfer, Token, TokenAccount, Transfer};
+
+#[derive(Accounts)]
+pub struct RegisterCranker<'info> {
+    pub stake_manager: Box<Account<'info, StakeManager>>,
+
+    #[account(
+        init,
+        space = 8 + std::mem::size_of::<CrankerAccount>(),
+        payer = rent_payer,
+        rent_exempt = enforce,
+        seeds = [
+            &stake_manager.key().to_bytes(),
+            &cranker.key().to_bytes(),
+            CrankerAccount::SEED,
+        ],
+        bump,
+    )]
+    pub cranker_account: Box<Account<'info, CrankerAccount>>,
+
+    pub cranker: Signer<'info>,
+
+    #[account(
+        mut,
+        owner = system_program::ID
+    )]
+    pub rent_payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct EventRegisterCranker {
+    pub stake_manager: Pubkey,
+    pub cranker: Pubkey,
+    pub cranker_account: Pubkey,
+}
+
+impl<'info> RegisterCranker<'info> {
+    pub fn process(&mut self, cranker_account_bump: u8) -> Result<()> {
+        self.cranker_account.set_inner(CrankerAccount {
+            stake_manager: self.stake_manager.key(),
+            cranker: self.cranker.key(),
+            bump: cranker_account_bump,
+            unclaimed_reward: 0,
+        });
+
+        emit!(EventRegisterCranker {
+            stake_manager: self.stake_manager.key(),
+            cranker: self.cranker.key(),
+            cranker_account: self.cranker_account.key(),
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ClaimCrankReward<'info> {
+    pub stake_manager: Box<Account<'info, StakeManager>>,
+
+    #[account(
+        seeds = [
+            &stake_manager.key().to_bytes(),
+            StakeManager::POOL_SEED,
+        ],
+        bump = stake_manager.pool_seed_bump
+    )]
+    pub stake_pool: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        has_one = cranker @ Errors::CrankerNotMatch,
+        seeds = [
+            &stake_manager.key().to_bytes(),
+            &cranker.key().to_bytes(),
+            CrankerAccount::SEED,
+        ],
+        bump = cranker_account.bump,
+    )]
+    pub cranker_account: Box<Account<'info, CrankerAccount>>,
+
+    pub cranker: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = stake_manager.lsd_token_mint,
+        associated_token::authority = stake_pool,
+    )]
+    pub crank_reward_escrow: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = stake_manager.lsd_token_mint
+    )]
+    pub claim_to: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[event]
+pub struct EventClaimCrankReward {
+    pub cranker: Pubkey,
+    pub claim_to: Pubkey,
+    pub claim_amount: u64,
+}
+
+impl<'info> ClaimCrankReward<'info> {
+    pub fn process(&mut self) -> Result<()> {
+        let claim_amount = self.cranker_account.unclaimed_reward;
+        require_gt!(claim_amount, 0, Errors::AmountUnmatch);
+
+        self.cranker_account.unclaimed_reward = 0;
+
+        transfer(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                Transfer {
+                    from: self.crank_reward_escrow.to_account_info(),
+                    to: self.claim_to.to_account_info(),
+                    authority: self.stake_pool.to_account_info(),
+                },
+                &[&[
+                    &self.stake_manager.key().to_bytes(),
+                    StakeManager::POOL_SEED,
+                    &[self.stake_manager.pool_seed_bump],
+                ]],
+            ),
+            claim_amount,
+        )?;
+
+        emit!(EventClaimCrankReward {
+            cranker: self.cranker.key(),
+            claim_to: self.claim_to.key(),
+            claim_amount
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,100 @@
+use crate::{DepositAllowlistAccount, Errors, StakeManager};
+use anchor_lang::{prelude::*, solana_program::system_program};
+
+#[derive(Accounts)]
+#[instruction(depositor: Pubkey)]
+pub struct AddDepositAllowlist<'info> {
+    #[account(
+        constraint = stake_manager.deposit_authority == Some(deposit_authority.key()) @ Errors::DepositAuthorityNotMatch
+    )]
+    pub stake_manager: Box<Account<'info, StakeManager>>,
+
+    pub deposit_authority: Signer<'info>,
+
+    #[account(
+        init,
+        space = 8 + std::mem::size_of::<DepositAllowlistAccount>(),
+        payer = rent_payer,
+        rent_exempt = enforce,
+        seeds = [
+            &stake_manager.key().to_bytes(),
+            &depositor.to_bytes(),
+            DepositAllowlistAccount::SEED,
+        ],
+        bump,
+    )]
+    pub deposit_allowlist_account: Box<Account<'info, DepositAllowlistAccount>>,
+
+    #[account(
+        mut,
+        owner = system_program::ID
+    )]
+    pub rent_payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct EventAddDepositAllowlist {
+    pub stake_manager: Pubkey,
+    pub depositor: Pubkey,
+}
+
+impl<'info> AddDepositAllowlist<'info> {
+    pub fn process(&mut self, depositor: Pubkey, deposit_allowlist_account_bump: u8) -> Result<()> {
+        self.deposit_allowlist_account
+            .set_inner(DepositAllowlistAccount {
+                stake_manager: self.stake_manager.key(),
+                depositor,
+                bump: deposit_allowlist_account_bump,
+            });
+
+        emit!(EventAddDepositAllowlist {
+            stake_manager: self.stake_manager.key(),
+            depositor
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct RemoveDepositAllowlist<'info> {
+    #[account(
+        constraint = stake_manager.deposit_authority == Some(deposit_authority.key()) @ Errors::DepositAuthorityNotMatch
+    )]
+    pub stake_manager: Box<Account<'info, StakeManager>>,
+
+    pub deposit_authority: Signer<'info>,
+
+    #[account(
+        mut,
+        close = rent_receiver,
+        seeds = [
+            &stake_manager.key().to_bytes(),
+            &deposit_allowlist_account.depositor.to_bytes(),
+            DepositAllowlistAccount::SEED,
+        ],
+        bump = deposit_allowlist_account.bump,
+    )]
+    pub deposit_allowlist_account: Box<Account<'info, DepositAllowlistAccount>>,
+
+    /// CHECK: receives the rent of the allowlist account
+    #[account(mut)]
+    pub rent_receiver: UncheckedAccount<'info>,
+}
+
+#[event]
+pub struct EventRemoveDepositAllowlist {
+    pub stake_manager: Pubkey,
+    pub depositor: Pubkey,
+}
+
+impl<'info> RemoveDepositAllowlist<'info> {
+    pub fn process(&mut self) -> Result<()> {
+        emit!(EventRemoveDepositAllowlist {
+            stake_manager: self.stake_manager.key(),
+            depositor: self.deposit_allowlist_account.depositor
+        });
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{Errors, StakeManager};
+use crate::{CrankerAccount, Errors, StakeManager};
 use anchor_lang::prelude::*;
 use anchor_lang::system_program::{transfer, Transfer};
 use anchor_lang::{
@@ -58,6 +58,20 @@ pub struct EraBond<'info> {
     pub stake_history: UncheckedAccount<'info>,
     pub stake_program: Program<'info, Stake>,
     pub system_program: Program<'info, System>,
+
+    pub cranker: Option<Signer<'info>>,
+
+    #[account(
+        mut,
+        has_one = cranker @ Errors::CrankerNotMatch,
+        seeds = [
+            &stake_manager.key().to_bytes(),
+            &cranker_account.cranker.to_bytes(),
+            CrankerAccount::SEED,
+        ],
+        bump = cranker_account.bump,
+    )]
+    pub cranker_account: Option<Box<Account<'info, CrankerAccount>>>,
 }
 
 #[event]
@@ -69,6 +83,7 @@ pub struct EventEraBond {
 
 impl<'info> EraBond<'info> {
     pub fn process(&mut self) -> Result<()> {
+        require!(!self.stake_manager.paused, Errors::StakeManagerPaused);
         require!(
             self.stake_manager
                 .era_process_data
@@ -90,6 +105,7 @@ impl<'info> EraBond<'info> {
         );
 
         let need_bond = self.stake_manager.era_process_data.need_bond;
+        let rent = self.stake_account.get_lamports();
         transfer(
             CpiContext::new_with_signer(
                 self.system_program.to_account_info(),
@@ -145,15 +161,30 @@ impl<'info> EraBond<'info> {
         )?;
 
         self.stake_manager.era_process_data.need_bond = 0;
+        self.stake_manager.tracked_pool_balance = self
+            .stake_manager
+            .tracked_pool_balance
+            .saturating_sub(need_bond);
         self.stake_manager
             .stake_accounts
             .push(self.stake_account.key());
+        let account_len = self.stake_manager.to_account_info().data_len();
+        self.stake_manager.add_rent_payer(
+            self.stake_account.key(),
+            self.rent_payer.key(),
+            rent,
+            account_len,
+        )?;
 
         self.stake_manager
             .era_process_data
             .pending_stake_accounts
             .push(self.stake_account.key());
 
+        if let Some(cranker_account) = self.cranker_account.as_mut() {
+            self.stake_manager.pay_crank_reward(cranker_account);
+        }
+
         emit!(EventEraBond {
             era: self.stake_manager.latest_era,
             stake_account: self.stake_account.key(),
@@ -0,0 +1,138 @@
+use crate::{Errors, StakeManager};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::stake_history;
+use anchor_spl::stake::{withdraw, Stake, StakeAccount, Withdraw};
+
+#[derive(Accounts)]
+pub struct EraClaimStakeExcess<'info> {
+    #[account(mut)]
+    pub stake_manager: Box<Account<'info, StakeManager>>,
+
+    #[account(
+        mut,
+        seeds = [
+            &stake_manager.key().to_bytes(),
+            StakeManager::POOL_SEED
+        ],
+        bump = stake_manager.pool_seed_bump
+    )]
+    pub stake_pool: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub stake_account: Account<'info, StakeAccount>,
+
+    pub clock: Sysvar<'info, Clock>,
+    /// CHECK: stake history
+    #[account(address = stake_history::ID)]
+    pub stake_history: UncheckedAccount<'info>,
+    pub stake_program: Program<'info, Stake>,
+}
+
+#[event]
+pub struct EventEraClaimExcess {
+    pub era: u64,
+    pub from: Pubkey,
+    pub excess_amount: u64,
+}
+
+impl<'info> EraClaimStakeExcess<'info> {
+    pub fn process(&mut self) -> Result<()> {
+        require!(!self.stake_manager.paused, Errors::StakeManagerPaused);
+        require_eq!(
+            self.stake_manager.version,
+            StakeManager::CURRENT_VERSION,
+            Errors::StakeManagerNotMigrated
+        );
+        require!(
+            self.stake_manager
+                .stake_accounts
+                .contains(&self.stake_account.key()),
+            Errors::StakeAccountNotExist
+        );
+
+        let delegation = self
+            .stake_account
+            .delegation()
+            .ok_or_else(|| error!(Errors::DelegationEmpty))?;
+        let rent_exempt_reserve = self
+            .stake_account
+            .meta()
+            .ok_or_else(|| error!(Errors::DelegationEmpty))?
+            .rent_exempt_reserve;
+
+        // lamports beyond stake and rent, such as mev tips
+        let excess_amount = self
+            .stake_account
+            .get_lamports()
+            .saturating_sub(delegation.stake + rent_exempt_reserve);
+        require_gt!(excess_amount, 0, Errors::AmountUnmatch);
+
+        withdraw(
+            CpiContext::new_with_signer(
+                self.stake_program.to_account_info(),
+                Withdraw {
+                    stake: self.stake_account.to_account_info(),
+                    withdrawer: self.stake_pool.to_account_info(),
+                    to: self.stake_pool.to_account_info(),
+                    clock: self.clock.to_account_info(),
+                    stake_history: self.stake_history.to_account_info(),
+                },
+                &[&[
+                    &self.stake_manager.key().to_bytes(),
+                    StakeManager::POOL_SEED,
+                    &[self.stake_manager.pool_seed_bump],
+                ]],
+            ),
+            excess_amount,
+            None,
+        )?;
+
+        self.stake_manager.add_excess_lamports(excess_amount);
+
+        emit!(EventEraClaimExcess {
+            era: self.stake_manager.latest_era,
+            from: self.stake_account.key(),
+            excess_amount
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct EraClaimPoolExcess<'info> {
+    #[account(mut)]
+    pub stake_manager: Box<Account<'info, StakeManager>>,
+
+    #[account(
+        seeds = [
+            &stake_manager.key().to_bytes(),
+            StakeManager::POOL_SEED
+        ],
+        bump = stake_manager.pool_seed_bump
+    )]
+    pub stake_pool: SystemAccount<'info>,
+}
+
+impl<'info> EraClaimPoolExcess<'info> {
+    pub fn process(&mut self) -> Result<()> {
+        require!(!self.stake_manager.paused, Errors::StakeManagerPaused);
+        require_eq!(
+            self.stake_manager.version,
+            StakeManager::CURRENT_VERSION,
+            Errors::StakeManagerNotMigrated
+        );
+        let excess_amount = self
+            .stake_manager
+            .calc_pool_excess(self.stake_pool.lamports());
+        require_gt!(excess_amount, 0, Errors::AmountUnmatch);
+
+        self.stake_manager.add_excess_lamports(excess_amount);
+
+        emit!(EventEraClaimExcess {
+            era: self.stake_manager.latest_era,
+            from: self.stake_pool.key(),
+            excess_amount
+        });
+        Ok(())
+    }
+}
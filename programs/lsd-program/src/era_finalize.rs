@@ -0,0 +1,42 @@
+use crate::*;
+
+#[derive(Accounts)]
+pub struct EraFinalize<'info> {
+    pub era_update_rate: EraUpdateRate<'info>,
+}
+
+impl<'info> EraFinalize<'info> {
+    // remaining accounts: every pending stake account of the era, rate is updated right after
+    pub fn process(&mut self, remaining_accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+        require_eq!(
+            remaining_accounts.len(),
+            self.era_update_rate
+                .stake_manager
+                .era_process_data
+                .pending_stake_accounts
+                .len(),
+            Errors::RemainingAccountsNotMatch
+        );
+        require_gte!(
+            StakeManager::FINALIZE_BATCH_LIMIT,
+            remaining_accounts.len() as u64,
+            Errors::RemainingAccountsNotMatch
+        );
+
+        if !remaining_accounts.is_empty() {
+            let (stake_accounts, stake_amount) = update_active_batch(
+                &mut self.era_update_rate.stake_manager,
+                &mut self.era_update_rate.cranker_account,
+                remaining_accounts,
+            )?;
+
+            emit!(EventEraUpdateActiveBatch {
+                era: self.era_update_rate.stake_manager.latest_era,
+                stake_accounts,
+                stake_amount
+            });
+        }
+
+        self.era_update_rate.process()
+    }
+}
@@ -1,8 +1,8 @@
-use crate::{Errors, StakeManager};
+use crate::{CrankerAccount, Errors, StakeManager};
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::sysvar::stake_history;
 use anchor_lang::solana_program::{program::invoke_signed, stake};
-use anchor_spl::stake::{Stake, StakeAccount};
+use anchor_spl::stake::{withdraw, Stake, StakeAccount, Withdraw};
 
 #[derive(Accounts)]
 pub struct EraMerge<'info> {
@@ -29,16 +29,37 @@ pub struct EraMerge<'info> {
     #[account(address = stake_history::ID)]
     pub stake_history: UncheckedAccount<'info>,
     pub stake_program: Program<'info, Stake>,
+
+    // required when the source account's rent payer is recorded
+    #[account(mut)]
+    pub rent_refund_to: Option<SystemAccount<'info>>,
+
+    pub cranker: Option<Signer<'info>>,
+
+    #[account(
+        mut,
+        has_one = cranker @ Errors::CrankerNotMatch,
+        seeds = [
+            &stake_manager.key().to_bytes(),
+            &cranker_account.cranker.to_bytes(),
+            CrankerAccount::SEED,
+        ],
+        bump = cranker_account.bump,
+    )]
+    pub cranker_account: Option<Box<Account<'info, CrankerAccount>>>,
 }
 
 #[event]
 pub struct EventEraMerge {
     pub src_stake_account: Pubkey,
     pub dst_stake_account: Pubkey,
+    pub rent_refund_to: Option<Pubkey>,
+    pub rent_refund: u64,
 }
 
 impl<'info> EraMerge<'info> {
     pub fn process(&mut self) -> Result<()> {
+        require!(!self.stake_manager.paused, Errors::StakeManagerPaused);
         require!(
             self.stake_manager.era_process_data.is_empty(),
             Errors::EraIsProcessing
@@ -111,9 +132,74 @@ impl<'info> EraMerge<'info> {
             .stake_accounts
             .retain(|&e| e != self.src_stake_account.key());
 
+        // the source rent lands in the destination as undelegated lamports, send it back
+        let (rent_refund_to, rent_refund) = match self
+            .stake_manager
+            .take_rent_payer(&self.src_stake_account.key())
+        {
+            Some(rent_payer) => {
+                let rent_refund_to = self
+                    .rent_refund_to
+                    .as_ref()
+                    .ok_or_else(|| error!(Errors::RentPayerNotMatch))?;
+                require_keys_eq!(
+                    rent_refund_to.key(),
+                    rent_payer.payer,
+                    Errors::RentPayerNotMatch
+                );
+
+                self.dst_stake_account.reload()?;
+                let dst_meta = self
+                    .dst_stake_account
+                    .meta()
+                    .ok_or_else(|| error!(Errors::DelegationEmpty))?;
+                let dst_stake = self
+                    .dst_stake_account
+                    .delegation()
+                    .ok_or_else(|| error!(Errors::DelegationEmpty))?
+                    .stake;
+                let rent_refund = rent_payer.rent.min(
+                    self.dst_stake_account
+                        .get_lamports()
+                        .saturating_sub(dst_stake + dst_meta.rent_exempt_reserve),
+                );
+
+                if rent_refund > 0 {
+                    withdraw(
+                        CpiContext::new_with_signer(
+                            self.stake_program.to_account_info(),
+                            Withdraw {
+                                stake: self.dst_stake_account.to_account_info(),
+                                withdrawer: self.stake_pool.to_account_info(),
+                                to: rent_refund_to.to_account_info(),
+                                clock: self.clock.to_account_info(),
+                                stake_history: self.stake_history.to_account_info(),
+                            },
+                            &[&[
+                                &self.stake_manager.key().to_bytes(),
+                                StakeManager::POOL_SEED,
+                                &[self.stake_manager.pool_seed_bump],
+                            ]],
+                        ),
+                        rent_refund,
+                        None,
+                    )?;
+                }
+
+                (Some(rent_payer.payer), rent_refund)
+            }
+            None => (None, 0),
+        };
+
+        if let Some(cranker_account) = self.cranker_account.as_mut() {
+            self.stake_manager.pay_crank_reward(cranker_account);
+        }
+
         emit!(EventEraMerge {
             src_stake_account: self.src_stake_account.key(),
-            dst_stake_account: self.dst_stake_account.key()
+            dst_stake_account: self.dst_stake_account.key(),
+            rent_refund_to,
+            rent_refund
         });
         Ok(())
     }
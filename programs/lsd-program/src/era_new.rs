@@ -1,4 +1,4 @@
-use crate::{EraProcessData, Errors, StakeManager};
+use crate::{CrankerAccount, EraProcessData, Errors, StakeManager};
 use anchor_lang::prelude::*;
 
 #[derive(Accounts)]
@@ -6,28 +6,63 @@ pub struct EraNew<'info> {
     #[account(mut)]
     pub stake_manager: Box<Account<'info, StakeManager>>,
 
+    #[account(
+        seeds = [
+            &stake_manager.key().to_bytes(),
+            StakeManager::POOL_SEED
+        ],
+        bump = stake_manager.pool_seed_bump
+    )]
+    pub stake_pool: SystemAccount<'info>,
+
     pub clock: Sysvar<'info, Clock>,
+
+    pub cranker: Option<Signer<'info>>,
+
+    #[account(
+        mut,
+        has_one = cranker @ Errors::CrankerNotMatch,
+        seeds = [
+            &stake_manager.key().to_bytes(),
+            &cranker_account.cranker.to_bytes(),
+            CrankerAccount::SEED,
+        ],
+        bump = cranker_account.bump,
+    )]
+    pub cranker_account: Option<Box<Account<'info, CrankerAccount>>>,
 }
 
 #[event]
 pub struct EventEraNew {
     pub new_era: u64,
+    pub era_epochs: u64,
     pub need_bond: u64,
     pub need_unbond: u64,
     pub active: u64,
+    pub bond_deferred: u64,
+    pub total_unstake_pending: u64,
+    pub total_unstake_claimable: u64,
 }
 
 impl<'info> EraNew<'info> {
-    pub fn process(&mut self) -> Result<()> {
-        let new_era = self.stake_manager.latest_era + 1;
+    // catch_up jumps to the current epoch instead of the next era, at most
+    // MAX_CATCH_UP_EPOCHS ahead so a longer outage takes several catch ups
+    pub fn process(&mut self, catch_up: bool) -> Result<()> {
+        require!(!self.stake_manager.paused, Errors::StakeManagerPaused);
+        require_eq!(
+            self.stake_manager.version,
+            StakeManager::CURRENT_VERSION,
+            Errors::StakeManagerNotMigrated
+        );
+        let next_era = self.stake_manager.latest_era + 1;
 
-        require_gte!(self.clock.epoch, new_era, Errors::EraIsLatest);
+        require_gte!(self.clock.epoch, next_era, Errors::EraIsLatest);
         require!(
             self.stake_manager.era_process_data.is_empty(),
             Errors::EraIsProcessing
         );
 
-        let (need_bond, need_unbond) =
+        let (era_need_bond, need_unbond) =
             if self.stake_manager.era_bond > self.stake_manager.era_unbond {
                 (
                     self.stake_manager.era_bond - self.stake_manager.era_unbond,
@@ -40,23 +75,46 @@ impl<'info> EraNew<'info> {
                 )
             };
 
+        // serve matured unstakes first, never bond lamports owed to unstakers
+        let pool_balance = self.stake_pool.lamports();
+        self.stake_manager.update_unstake_fulfilled(pool_balance);
+        let need_bond = era_need_bond.min(self.stake_manager.calc_bondable(pool_balance));
+        // the rest stays active in the pool and is bonded next era
+        let bond_deferred = era_need_bond - need_bond;
+
+        let new_era = if catch_up {
+            self.stake_manager.calc_catch_up_era(self.clock.epoch)
+        } else {
+            next_era
+        };
+        let era_epochs = new_era - self.stake_manager.latest_era;
+
         self.stake_manager.latest_era = new_era;
-        self.stake_manager.era_bond = 0;
+        self.stake_manager.era_epochs = era_epochs;
+        self.stake_manager.era_bond = bond_deferred;
         self.stake_manager.era_unbond = 0;
 
         self.stake_manager.era_process_data = EraProcessData {
             need_bond,
             need_unbond,
-            old_active: self.stake_manager.active,
+            old_active: self.stake_manager.active - bond_deferred,
             new_active: 0,
             pending_stake_accounts: self.stake_manager.stake_accounts.clone(),
         };
 
+        if let Some(cranker_account) = self.cranker_account.as_mut() {
+            self.stake_manager.pay_crank_reward(cranker_account);
+        }
+
         emit!(EventEraNew {
             new_era,
+            era_epochs,
             need_bond,
             need_unbond,
-            active: self.stake_manager.active
+            active: self.stake_manager.active,
+            bond_deferred,
+            total_unstake_pending: self.stake_manager.total_unstake_pending,
+            total_unstake_claimable: self.stake_manager.total_unstake_claimable
         });
         Ok(())
     }
@@ -1,4 +1,4 @@
-use crate::{Errors, StakeManager};
+use crate::{CrankerAccount, Errors, StakeManager};
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::stake::tools;
 use anchor_spl::stake::Stake;
@@ -9,6 +9,20 @@ pub struct EraSkipBond<'info> {
     pub stake_manager: Box<Account<'info, StakeManager>>,
 
     pub stake_program: Program<'info, Stake>,
+
+    pub cranker: Option<Signer<'info>>,
+
+    #[account(
+        mut,
+        has_one = cranker @ Errors::CrankerNotMatch,
+        seeds = [
+            &stake_manager.key().to_bytes(),
+            &cranker_account.cranker.to_bytes(),
+            CrankerAccount::SEED,
+        ],
+        bump = cranker_account.bump,
+    )]
+    pub cranker_account: Option<Box<Account<'info, CrankerAccount>>>,
 }
 
 #[event]
@@ -19,6 +33,7 @@ pub struct EventEraSkipBond {
 
 impl<'info> EraSkipBond<'info> {
     pub fn process(&mut self) -> Result<()> {
+        require!(!self.stake_manager.paused, Errors::StakeManagerPaused);
         require!(
             self.stake_manager
                 .era_process_data
@@ -32,6 +47,10 @@ impl<'info> EraSkipBond<'info> {
         self.stake_manager.era_process_data.new_active += need_bond;
         self.stake_manager.era_process_data.need_bond = 0;
 
+        if let Some(cranker_account) = self.cranker_account.as_mut() {
+            self.stake_manager.pay_crank_reward(cranker_account);
+        }
+
         emit!(EventEraSkipBond {
             era: self.stake_manager.latest_era,
             skip_bond_amount: need_bond
@@ -1,4 +1,4 @@
-use crate::{Errors, StakeManager};
+use crate::{CrankerAccount, Errors, StakeManager, UnbondingSplit};
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::sysvar::stake_history;
 use anchor_lang::{
@@ -55,6 +55,20 @@ pub struct EraUnbond<'info> {
     pub stake_history: UncheckedAccount<'info>,
     pub stake_program: Program<'info, Stake>,
     pub system_program: Program<'info, System>,
+
+    pub cranker: Option<Signer<'info>>,
+
+    #[account(
+        mut,
+        has_one = cranker @ Errors::CrankerNotMatch,
+        seeds = [
+            &stake_manager.key().to_bytes(),
+            &cranker_account.cranker.to_bytes(),
+            CrankerAccount::SEED,
+        ],
+        bump = cranker_account.bump,
+    )]
+    pub cranker_account: Option<Box<Account<'info, CrankerAccount>>>,
 }
 
 #[event]
@@ -67,6 +81,7 @@ pub struct EventEraUnbond {
 
 impl<'info> EraUnbond<'info> {
     pub fn process(&mut self) -> Result<()> {
+        require!(!self.stake_manager.paused, Errors::StakeManagerPaused);
         require!(
             self.stake_manager.era_process_data.need_unbond(),
             Errors::EraNoNeedUnBond
@@ -129,6 +144,14 @@ impl<'info> EraUnbond<'info> {
 
             (self.from_stake_account.to_account_info(), delegation.stake)
         } else {
+            let account_len = self.stake_manager.to_account_info().data_len();
+            self.stake_manager.add_rent_payer(
+                self.split_stake_account.key(),
+                self.rent_payer.key(),
+                self.split_stake_account.get_lamports(),
+                account_len,
+            )?;
+
             // split
             let split_instruction = stake::instruction::split(
                 self.from_stake_account.to_account_info().key,
@@ -179,9 +202,20 @@ impl<'info> EraUnbond<'info> {
         self.stake_manager
             .split_accounts
             .push(will_deactive_account.key());
+        // a catch up era funds the unstakes of every era it covers
+        let era = self.stake_manager.latest_era_start();
+        self.stake_manager.unbonding_splits.push(UnbondingSplit {
+            split_account: will_deactive_account.key(),
+            era,
+            deactivation_epoch: self.clock.epoch,
+        });
 
         self.stake_manager.era_process_data.need_unbond -= will_deactive_amount;
 
+        if let Some(cranker_account) = self.cranker_account.as_mut() {
+            self.stake_manager.pay_crank_reward(cranker_account);
+        }
+
         emit!(EventEraUnbond {
             era: self.stake_manager.latest_era,
             from_stake_account: self.from_stake_account.key(),
@@ -1,4 +1,4 @@
-use crate::{Errors, StakeManager};
+use crate::{CrankerAccount, Errors, StakeManager};
 use anchor_lang::prelude::*;
 use anchor_spl::stake::StakeAccount;
 
@@ -8,6 +8,20 @@ pub struct EraUpdateActive<'info> {
     pub stake_manager: Box<Account<'info, StakeManager>>,
 
     pub stake_account: Account<'info, StakeAccount>,
+
+    pub cranker: Option<Signer<'info>>,
+
+    #[account(
+        mut,
+        has_one = cranker @ Errors::CrankerNotMatch,
+        seeds = [
+            &stake_manager.key().to_bytes(),
+            &cranker_account.cranker.to_bytes(),
+            CrankerAccount::SEED,
+        ],
+        bump = cranker_account.bump,
+    )]
+    pub cranker_account: Option<Box<Account<'info, CrankerAccount>>>,
 }
 
 #[event]
@@ -19,43 +33,129 @@ pub struct EventEraUpdateActive {
 
 impl<'info> EraUpdateActive<'info> {
     pub fn process(&mut self) -> Result<()> {
-        require!(
-            self.stake_manager.era_process_data.need_update_active(),
-            Errors::EraNoNeedUpdateActive
-        );
+        let stake_amount = update_active(&mut self.stake_manager, &self.stake_account)?;
 
-        require!(
-            self.stake_manager
-                .era_process_data
-                .pending_stake_accounts
-                .contains(&self.stake_account.key()),
-            Errors::StakeAccountNotExist
-        );
+        if let Some(cranker_account) = self.cranker_account.as_mut() {
+            self.stake_manager.pay_crank_reward(cranker_account);
+        }
 
-        let delegation = self
-            .stake_account
-            .delegation()
-            .ok_or_else(|| error!(Errors::DelegationEmpty))?;
+        emit!(EventEraUpdateActive {
+            era: self.stake_manager.latest_era,
+            stake_account: self.stake_account.key(),
+            stake_amount
+        });
+        Ok(())
+    }
+}
 
-        // require stake is active (deactivation_epoch == u64::MAX)
-        require_eq!(
-            delegation.deactivation_epoch,
-            std::u64::MAX,
-            Errors::StakeAccountNotActive
-        );
+// count a pending stake account into new_active, returns its stake amount
+pub fn update_active(
+    stake_manager: &mut StakeManager,
+    stake_account: &Account<StakeAccount>,
+) -> Result<u64> {
+    require!(!stake_manager.paused, Errors::StakeManagerPaused);
+    require!(
+        stake_manager.era_process_data.need_update_active(),
+        Errors::EraNoNeedUpdateActive
+    );
 
-        self.stake_manager
+    require!(
+        stake_manager
             .era_process_data
             .pending_stake_accounts
-            .retain(|&e| e != self.stake_account.key());
+            .contains(&stake_account.key()),
+        Errors::StakeAccountNotExist
+    );
 
-        self.stake_manager.era_process_data.new_active += delegation.stake;
+    let delegation = stake_account
+        .delegation()
+        .ok_or_else(|| error!(Errors::DelegationEmpty))?;
 
-        emit!(EventEraUpdateActive {
+    // require stake is active (deactivation_epoch == u64::MAX)
+    require_eq!(
+        delegation.deactivation_epoch,
+        std::u64::MAX,
+        Errors::StakeAccountNotActive
+    );
+
+    stake_manager
+        .era_process_data
+        .pending_stake_accounts
+        .retain(|&e| e != stake_account.key());
+
+    stake_manager.era_process_data.new_active += delegation.stake;
+
+    Ok(delegation.stake)
+}
+
+#[derive(Accounts)]
+pub struct EraUpdateActiveBatch<'info> {
+    #[account(mut)]
+    pub stake_manager: Box<Account<'info, StakeManager>>,
+
+    pub cranker: Option<Signer<'info>>,
+
+    #[account(
+        mut,
+        has_one = cranker @ Errors::CrankerNotMatch,
+        seeds = [
+            &stake_manager.key().to_bytes(),
+            &cranker_account.cranker.to_bytes(),
+            CrankerAccount::SEED,
+        ],
+        bump = cranker_account.bump,
+    )]
+    pub cranker_account: Option<Box<Account<'info, CrankerAccount>>>,
+}
+
+#[event]
+pub struct EventEraUpdateActiveBatch {
+    pub era: u64,
+    pub stake_accounts: Vec<Pubkey>,
+    pub stake_amount: u64,
+}
+
+impl<'info> EraUpdateActiveBatch<'info> {
+    // remaining accounts: pending stake accounts
+    pub fn process(&mut self, remaining_accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+        require!(
+            !remaining_accounts.is_empty()
+                && remaining_accounts.len() as u64 <= StakeManager::UPDATE_ACTIVE_BATCH_LIMIT,
+            Errors::RemainingAccountsNotMatch
+        );
+
+        let (stake_accounts, stake_amount) = update_active_batch(
+            &mut self.stake_manager,
+            &mut self.cranker_account,
+            remaining_accounts,
+        )?;
+
+        emit!(EventEraUpdateActiveBatch {
             era: self.stake_manager.latest_era,
-            stake_account: self.stake_account.key(),
-            stake_amount: delegation.stake
+            stake_accounts,
+            stake_amount
         });
         Ok(())
     }
 }
+
+// one crank reward per stake account, like separate era_update_active calls
+pub fn update_active_batch<'info>(
+    stake_manager: &mut StakeManager,
+    cranker_account: &mut Option<Box<Account<'info, CrankerAccount>>>,
+    remaining_accounts: &'info [AccountInfo<'info>],
+) -> Result<(Vec<Pubkey>, u64)> {
+    let mut stake_accounts = Vec::with_capacity(remaining_accounts.len());
+    let mut stake_amount: u64 = 0;
+    for account_info in remaining_accounts.iter() {
+        let stake_account = Account::<StakeAccount>::try_from(account_info)?;
+        stake_amount += update_active(stake_manager, &stake_account)?;
+        stake_accounts.push(stake_account.key());
+
+        if let Some(cranker_account) = cranker_account.as_mut() {
+            stake_manager.pay_crank_reward(cranker_account);
+        }
+    }
+
+    Ok((stake_accounts, stake_amount))
+}
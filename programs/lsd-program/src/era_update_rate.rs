@@ -1,8 +1,8 @@
-use crate::{EraRate, Errors, Stack, StackFeeAccount, StakeManager};
+use crate::{CrankerAccount, EraRate, Errors, Stack, StackFeeAccount, StakeManager};
 use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
-    token::{mint_to, Mint, MintTo, Token, TokenAccount},
+    token::{burn, mint_to, Burn, Mint, MintTo, Token, TokenAccount},
 };
 
 #[derive(Accounts)]
@@ -30,16 +30,24 @@ pub struct EraUpdateRate<'info> {
     #[account(
         mut,
         associated_token::mint = lsd_token_mint,
-        associated_token::authority = stake_manager.admin,
+        associated_token::authority = stake_manager.platform_fee_recipient,
     )]
     pub platform_fee_recipient: Box<Account<'info, TokenAccount>>,
 
     #[account(
         mut,
         associated_token::mint = lsd_token_mint,
-        associated_token::authority = stack.admin,
+        associated_token::authority = stack_fee_account,
     )]
-    pub stack_fee_recipient: Box<Account<'info, TokenAccount>>,
+    pub stack_fee_escrow: Box<Account<'info, TokenAccount>>,
+
+    // also holds the insurance buffer and the crank budget
+    #[account(
+        mut,
+        associated_token::mint = lsd_token_mint,
+        associated_token::authority = stake_pool,
+    )]
+    pub referral_fee_escrow: Box<Account<'info, TokenAccount>>,
 
     #[account(
         mut,
@@ -53,35 +61,101 @@ pub struct EraUpdateRate<'info> {
 
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub token_program: Program<'info, Token>,
+
+    pub cranker: Option<Signer<'info>>,
+
+    #[account(
+        mut,
+        has_one = cranker @ Errors::CrankerNotMatch,
+        seeds = [
+            &stake_manager.key().to_bytes(),
+            &cranker_account.cranker.to_bytes(),
+            CrankerAccount::SEED,
+        ],
+        bump = cranker_account.bump,
+    )]
+    pub cranker_account: Option<Box<Account<'info, CrankerAccount>>>,
 }
 
 #[event]
 pub struct EventEraUpdateRate {
     pub era: u64,
     pub rate: u64,
+    pub era_epochs: u64,
     pub platform_fee: u64,
     pub stack_fee: u64,
+    pub referral_fee: u64,
+    pub insurance_fee: u64,
+    pub crank_fee: u64,
+    pub excess_reward: u64,
+}
+
+#[event]
+pub struct EventRateChangeAlert {
+    pub era: u64,
+    pub old_rate: u64,
+    pub new_rate: u64,
+    pub rate_change: u64,
+    pub rate_change_limit: u64,
+}
+
+#[event]
+pub struct EventEraLoss {
+    pub era: u64,
+    pub loss: u64,
+    pub insurance_burned: u64, // lsd token amount burned from the insurance buffer
+    pub insurance_buffer: u64,
 }
 
 impl<'info> EraUpdateRate<'info> {
     pub fn process(&mut self) -> Result<()> {
+        require!(!self.stake_manager.paused, Errors::StakeManagerPaused);
+        self.update_rate(true)?;
+
+        if let Some(cranker_account) = self.cranker_account.as_mut() {
+            self.stake_manager.pay_crank_reward(cranker_account);
+        }
+        Ok(())
+    }
+
+    pub fn update_rate(&mut self, check_rate_change_limit: bool) -> Result<()> {
         require!(
             self.stake_manager.era_process_data.need_update_rate(),
             Errors::EraNoNeedUpdateRate
         );
 
-        let reward = if self.stake_manager.era_process_data.new_active
-            > self.stake_manager.era_process_data.old_active
-        {
-            self.stake_manager.era_process_data.new_active
-                - self.stake_manager.era_process_data.old_active
+        let old_active = self.stake_manager.era_process_data.old_active;
+        let era_new_active = self.stake_manager.era_process_data.new_active;
+        // excess lamports are in active already, only count them as reward
+        let excess_reward = self.stake_manager.excess_reward;
+        let reward = era_new_active.saturating_sub(old_active) + excess_reward;
+        let loss = old_active.saturating_sub(era_new_active);
+
+        let cal_temp = self.stake_manager.active + era_new_active;
+        let new_active = if cal_temp > old_active {
+            cal_temp - old_active
         } else {
             0
         };
 
-        let platform_fee_raw = self.stake_manager.calc_platform_fee(reward)?;
-        let stack_fee = self.stack.calc_stack_fee(platform_fee_raw)?;
-        let platform_fee = platform_fee_raw - stack_fee;
+        // deposit and unstake fees count as supply already
+        let pending_fee = self.stake_manager.pending_fee;
+        let lsd_token_supply = self.lsd_token_mint.supply + pending_fee;
+        let reward_fee =
+            self.stake_manager
+                .calc_platform_fee(reward, new_active, lsd_token_supply)?;
+        let platform_fee_raw = reward_fee + pending_fee;
+        let stack_fee = self.stake_manager.calc_stack_fee(platform_fee_raw)?;
+        let referral_fee = self
+            .stake_manager
+            .calc_referral_fee(platform_fee_raw - stack_fee)?;
+        let insurance_fee = self
+            .stake_manager
+            .calc_insurance_fee(platform_fee_raw - stack_fee - referral_fee)?;
+        let crank_fee = self
+            .stake_manager
+            .calc_crank_fee(platform_fee_raw - stack_fee - referral_fee - insurance_fee)?;
+        let platform_fee = platform_fee_raw - stack_fee - referral_fee - insurance_fee - crank_fee;
 
         if platform_fee > 0 {
             mint_to(
@@ -109,7 +183,7 @@ impl<'info> EraUpdateRate<'info> {
                     self.token_program.to_account_info(),
                     MintTo {
                         mint: self.lsd_token_mint.to_account_info(),
-                        to: self.stack_fee_recipient.to_account_info(),
+                        to: self.stack_fee_escrow.to_account_info(),
                         authority: self.stake_pool.to_account_info(),
                     },
                     &[&[
@@ -123,29 +197,103 @@ impl<'info> EraUpdateRate<'info> {
 
             self.stack_fee_account.amount += stack_fee;
         }
+        if referral_fee + insurance_fee + crank_fee > 0 {
+            mint_to(
+                CpiContext::new_with_signer(
+                    self.token_program.to_account_info(),
+                    MintTo {
+                        mint: self.lsd_token_mint.to_account_info(),
+                        to: self.referral_fee_escrow.to_account_info(),
+                        authority: self.stake_pool.to_account_info(),
+                    },
+                    &[&[
+                        &self.stake_manager.key().to_bytes(),
+                        StakeManager::POOL_SEED,
+                        &[self.stake_manager.pool_seed_bump],
+                    ]],
+                ),
+                referral_fee + insurance_fee + crank_fee,
+            )?;
+
+            self.stake_manager.distribute_referral_fee(referral_fee);
+            self.stake_manager.insurance_buffer += insurance_fee;
+            self.stake_manager.crank_budget += crank_fee;
+        }
 
-        let cal_temp = self.stake_manager.active + self.stake_manager.era_process_data.new_active;
-        let new_active = if cal_temp > self.stake_manager.era_process_data.old_active {
-            cal_temp - self.stake_manager.era_process_data.old_active
-        } else {
-            0
-        };
+        let mut new_lsd_token_supply = lsd_token_supply + reward_fee;
+        if loss > 0 {
+            let insurance_burned = self
+                .stake_manager
+                .calc_insurance_burn(new_active, new_lsd_token_supply)?;
+            if insurance_burned > 0 {
+                burn(
+                    CpiContext::new_with_signer(
+                        self.token_program.to_account_info(),
+                        Burn {
+                            mint: self.lsd_token_mint.to_account_info(),
+                            from: self.referral_fee_escrow.to_account_info(),
+                            authority: self.stake_pool.to_account_info(),
+                        },
+                        &[&[
+                            &self.stake_manager.key().to_bytes(),
+                            StakeManager::POOL_SEED,
+                            &[self.stake_manager.pool_seed_bump],
+                        ]],
+                    ),
+                    insurance_burned,
+                )?;
+
+                self.stake_manager.insurance_buffer -= insurance_burned;
+                new_lsd_token_supply -= insurance_burned;
+            }
+
+            emit!(EventEraLoss {
+                era: self.stake_manager.latest_era,
+                loss,
+                insurance_burned,
+                insurance_buffer: self.stake_manager.insurance_buffer
+            });
+        }
 
         let new_rate = self
             .stake_manager
-            .calc_rate(new_active, self.lsd_token_mint.supply)?;
+            .calc_rate(new_active, new_lsd_token_supply)?;
         let rate_change = self
             .stake_manager
             .calc_rate_change(self.stake_manager.rate, new_rate)?;
 
-        if self.stake_manager.rate_change_limit > 0 {
-            require_gte!(
-                self.stake_manager.rate_change_limit,
+        // the limit is per epoch, a catch up era covers several
+        let era_epochs = self.stake_manager.era_epochs.max(1);
+        let rate_change_limit = self
+            .stake_manager
+            .rate_change_limit_of(new_rate)
+            .saturating_mul(era_epochs);
+        if rate_change_limit > 0 && rate_change > rate_change_limit {
+            // events of the failing transaction are discarded by indexers, monitors parse this
+            // line from its logs instead, force_era_update_rate emits EventRateChangeAlert
+            if check_rate_change_limit {
+                msg!(
+                    "RateChangeAlert: era={} old_rate={} new_rate={} rate_change={} rate_change_limit={}",
+                    self.stake_manager.latest_era,
+                    self.stake_manager.rate,
+                    new_rate,
+                    rate_change,
+                    rate_change_limit
+                );
+            }
+            require!(!check_rate_change_limit, Errors::RateChangeOverLimit);
+
+            emit!(EventRateChangeAlert {
+                era: self.stake_manager.latest_era,
+                old_rate: self.stake_manager.rate,
+                new_rate,
                 rate_change,
-                Errors::RateChangeOverLimit
-            );
+                rate_change_limit
+            });
         }
 
+        self.stake_manager.pending_fee = 0;
+        self.stake_manager.excess_reward = 0;
         self.stake_manager.era_process_data.old_active = 0;
         self.stake_manager.era_process_data.new_active = 0;
         self.stake_manager.active = new_active;
@@ -155,6 +303,7 @@ impl<'info> EraUpdateRate<'info> {
         self.stake_manager.era_rates.push(EraRate {
             era: latest_era,
             rate: new_rate,
+            era_epochs,
         });
         if self.stake_manager.era_rates.len() > StakeManager::ERA_RATES_LEN_LIMIT as usize {
             self.stake_manager.era_rates.remove(0);
@@ -163,8 +312,52 @@ impl<'info> EraUpdateRate<'info> {
         emit!(EventEraUpdateRate {
             era: latest_era,
             rate: new_rate,
+            era_epochs,
             platform_fee: platform_fee,
             stack_fee: stack_fee,
+            referral_fee,
+            insurance_fee,
+            crank_fee,
+            excess_reward,
+        });
+        Ok(())
+    }
+}
+
+// finish an era stuck on a rate change over the limit, admin may be a governance signer
+#[derive(Accounts)]
+pub struct ForceEraUpdateRate<'info> {
+    pub era_update_rate: EraUpdateRate<'info>,
+
+    #[account(
+        address = era_update_rate.stake_manager.admin @ Errors::AdminNotMatch
+    )]
+    pub admin: Signer<'info>,
+}
+
+#[event]
+pub struct EventForceEraUpdateRate {
+    pub era: u64,
+    pub old_rate: u64,
+    pub new_rate: u64,
+}
+
+impl<'info> ForceEraUpdateRate<'info> {
+    // expected_rate acknowledges the rate about to be applied
+    pub fn process(&mut self, expected_rate: u64) -> Result<()> {
+        let old_rate = self.era_update_rate.stake_manager.rate;
+
+        self.era_update_rate.update_rate(false)?;
+        require_eq!(
+            self.era_update_rate.stake_manager.rate,
+            expected_rate,
+            Errors::ForceRateNotMatch
+        );
+
+        emit!(EventForceEraUpdateRate {
+            era: self.era_update_rate.stake_manager.latest_era,
+            old_rate,
+            new_rate: self.era_update_rate.stake_manager.rate
         });
         Ok(())
     }
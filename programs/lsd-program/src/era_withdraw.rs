@@ -1,4 +1,4 @@
-use crate::{Errors, StakeManager};
+use crate::{CrankerAccount, Errors, StakeManager};
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::sysvar::stake_history;
 use anchor_spl::stake::{withdraw, Stake, StakeAccount, Withdraw};
@@ -26,6 +26,24 @@ pub struct EraWithdraw<'info> {
     #[account(address = stake_history::ID)]
     pub stake_history: UncheckedAccount<'info>,
     pub stake_program: Program<'info, Stake>,
+
+    // required when the split account's rent payer is recorded
+    #[account(mut)]
+    pub rent_refund_to: Option<SystemAccount<'info>>,
+
+    pub cranker: Option<Signer<'info>>,
+
+    #[account(
+        mut,
+        has_one = cranker @ Errors::CrankerNotMatch,
+        seeds = [
+            &stake_manager.key().to_bytes(),
+            &cranker_account.cranker.to_bytes(),
+            CrankerAccount::SEED,
+        ],
+        bump = cranker_account.bump,
+    )]
+    pub cranker_account: Option<Box<Account<'info, CrankerAccount>>>,
 }
 
 #[event]
@@ -33,10 +51,14 @@ pub struct EventEraWithdraw {
     pub era: u64,
     pub stake_account: Pubkey,
     pub withdraw_amount: u64,
+    pub excess_amount: u64,
+    pub rent_refund_to: Option<Pubkey>,
+    pub rent_refund: u64,
 }
 
 impl<'info> EraWithdraw<'info> {
     pub fn process(&mut self) -> Result<()> {
+        require!(!self.stake_manager.paused, Errors::StakeManagerPaused);
         require!(
             self.stake_manager
                 .split_accounts
@@ -55,7 +77,44 @@ impl<'info> EraWithdraw<'info> {
             Errors::StakeAccountActive
         );
 
-        let withdraw_amount = self.stake_account.get_lamports();
+        let rent_exempt_reserve = self
+            .stake_account
+            .meta()
+            .ok_or_else(|| error!(Errors::DelegationEmpty))?
+            .rent_exempt_reserve;
+        let total_amount = self.stake_account.get_lamports();
+        // a redelegate source keeps its delegation after the stake moved out, so rent is
+        // taken from the lamports first and only the rest can be stake
+        let rent_amount = total_amount.min(rent_exempt_reserve);
+        let stake_amount = (total_amount - rent_amount).min(delegation.stake);
+        // lamports beyond stake and rent landed in the split account as tips
+        let excess_amount = total_amount - rent_amount - stake_amount;
+
+        let (rent_refund_to, rent_refund) = match self
+            .stake_manager
+            .take_rent_payer(&self.stake_account.key())
+        {
+            Some(rent_payer) => {
+                let rent_refund_to = self
+                    .rent_refund_to
+                    .as_ref()
+                    .ok_or_else(|| error!(Errors::RentPayerNotMatch))?;
+                require_keys_eq!(
+                    rent_refund_to.key(),
+                    rent_payer.payer,
+                    Errors::RentPayerNotMatch
+                );
+
+                (
+                    Some(rent_refund_to.to_account_info()),
+                    rent_payer.rent.min(rent_amount),
+                )
+            }
+            None => (None, 0),
+        };
+        // rent without a recorded payer is counted as yield together with the excess
+        let rent_yield = rent_amount - rent_refund;
+
         withdraw(
             CpiContext::new_with_signer(
                 self.stake_program.to_account_info(),
@@ -72,18 +131,54 @@ impl<'info> EraWithdraw<'info> {
                     &[self.stake_manager.pool_seed_bump],
                 ]],
             ),
-            withdraw_amount,
+            total_amount - rent_refund,
             None,
         )?;
 
+        if let Some(rent_refund_to) = rent_refund_to.as_ref().filter(|_| rent_refund > 0) {
+            withdraw(
+                CpiContext::new_with_signer(
+                    self.stake_program.to_account_info(),
+                    Withdraw {
+                        stake: self.stake_account.to_account_info(),
+                        withdrawer: self.stake_pool.to_account_info(),
+                        to: rent_refund_to.clone(),
+                        clock: self.clock.to_account_info(),
+                        stake_history: self.stake_history.to_account_info(),
+                    },
+                    &[&[
+                        &self.stake_manager.key().to_bytes(),
+                        StakeManager::POOL_SEED,
+                        &[self.stake_manager.pool_seed_bump],
+                    ]],
+                ),
+                rent_refund,
+                None,
+            )?;
+        }
+
+        self.stake_manager.tracked_pool_balance += stake_amount;
+        self.stake_manager
+            .add_excess_lamports(excess_amount + rent_yield);
+
         self.stake_manager
             .split_accounts
             .retain(|&e| e != self.stake_account.key());
+        self.stake_manager
+            .unbonding_splits
+            .retain(|e| e.split_account != self.stake_account.key());
+
+        if let Some(cranker_account) = self.cranker_account.as_mut() {
+            self.stake_manager.pay_crank_reward(cranker_account);
+        }
 
         emit!(EventEraWithdraw {
             era: self.stake_manager.latest_era,
             stake_account: self.stake_account.key(),
-            withdraw_amount
+            withdraw_amount: total_amount - rent_refund,
+            excess_amount,
+            rent_refund_to: rent_refund_to.map(|e| e.key()),
+            rent_refund
         });
         Ok(())
     }
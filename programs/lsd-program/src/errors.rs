@@ -142,4 +142,124 @@ pub enum Errors {
 
     #[msg("Stake accounts len over limit")]
     StakeAccountsLenOverLimit,
+
+    #[msg("Rent payer not match")]
+    RentPayerNotMatch,
+
+    #[msg("Referral fee commission over limit")]
+    ReferralFeeCommissionOverLimit,
+
+    #[msg("Referrer not match")]
+    ReferrerNotMatch,
+
+    #[msg("Deposit fee over limit")]
+    DepositFeeOverLimit,
+
+    #[msg("Unstake fee over limit")]
+    UnstakeFeeOverLimit,
+
+    #[msg("Platform fee commission over limit")]
+    PlatformFeeCommissionOverLimit,
+
+    #[msg("Stack fee commission over limit")]
+    StackFeeCommissionOverLimit,
+
+    #[msg("Rate change limit out of range")]
+    RateChangeLimitOutOfRange,
+
+    #[msg("Unbonding duration out of range")]
+    UnbondingDurationOutOfRange,
+
+    #[msg("Min stake amount out of range")]
+    MinStakeAmountOutOfRange,
+
+    #[msg("Config timelock over limit")]
+    ConfigTimelockOverLimit,
+
+    #[msg("Pending config not exist")]
+    PendingConfigNotExist,
+
+    #[msg("Pending config not ready")]
+    PendingConfigNotReady,
+
+    #[msg("Governance signers invalid")]
+    GovernanceSignersInvalid,
+
+    #[msg("Governance threshold invalid")]
+    GovernanceThresholdInvalid,
+
+    #[msg("Governance signer not match")]
+    GovernanceSignerNotMatch,
+
+    #[msg("Proposal already executed")]
+    ProposalAlreadyExecuted,
+
+    #[msg("Proposal not approved")]
+    ProposalNotApproved,
+
+    #[msg("Proposal signers changed")]
+    ProposalSignersChanged,
+
+    #[msg("Role not match")]
+    RoleNotMatch,
+
+    #[msg("Stake manager paused")]
+    StakeManagerPaused,
+
+    #[msg("Deposit not allowed")]
+    DepositNotAllowed,
+
+    #[msg("Deposit authority not match")]
+    DepositAuthorityNotMatch,
+
+    #[msg("Pool capacity exceeded")]
+    PoolCapacityExceeded,
+
+    #[msg("Epoch deposit cap exceeded")]
+    EpochDepositCapExceeded,
+
+    #[msg("Insurance fee commission over limit")]
+    InsuranceFeeCommissionOverLimit,
+
+    #[msg("Force rate not match")]
+    ForceRateNotMatch,
+
+    #[msg("Pool insolvent")]
+    PoolInsolvent,
+
+    #[msg("Crank fee commission over limit")]
+    CrankFeeCommissionOverLimit,
+
+    #[msg("Cranker not match")]
+    CrankerNotMatch,
+
+    #[msg("Referred account not match")]
+    ReferredAccountNotMatch,
+
+    #[msg("Proposal too large")]
+    ProposalTooLarge,
+
+    #[msg("Proposal already cancelled")]
+    ProposalAlreadyCancelled,
+
+    #[msg("Proposal not closable")]
+    ProposalNotClosable,
+
+    #[msg("Crank reward over limit")]
+    CrankRewardOverLimit,
+
+    #[msg("Rent payers len over limit")]
+    RentPayersLenOverLimit,
+
+    #[msg("Escrow insufficient")]
+    EscrowInsufficient,
+
+    #[msg("Stake manager not migrated")]
+    StakeManagerNotMigrated,
+
+    #[msg("Stake manager already migrated")]
+    StakeManagerAlreadyMigrated,
+
+    #[msg("Unstake account already migrated")]
+    UnstakeAccountAlreadyMigrated,
 }
@@ -0,0 +1,364 @@
+use crate::{Errors, Governance, Proposal, ProposalAccount};
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        instruction::{AccountMeta, Instruction},
+        program::invoke_signed,
+        system_program,
+    },
+};
+
+#[derive(Accounts)]
+pub struct CreateGovernance<'info> {
+    #[account(
+        init,
+        space = Governance::SPACE,
+        payer = rent_payer,
+        rent_exempt = enforce,
+    )]
+    pub governance: Box<Account<'info, Governance>>,
+
+    /// CHECK: pda, set as admin of stack or stake manager to hand it over to governance
+    #[account(
+        seeds = [
+            &governance.key().to_bytes(),
+            Governance::SIGNER_SEED,
+        ],
+        bump,
+    )]
+    pub governance_signer: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        owner = system_program::ID
+    )]
+    pub rent_payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct EventCreateGovernance {
+    pub governance: Pubkey,
+    pub governance_signer: Pubkey,
+    pub signers: Vec<Pubkey>,
+    pub threshold: u8,
+}
+
+impl<'info> CreateGovernance<'info> {
+    pub fn process(
+        &mut self,
+        signers: Vec<Pubkey>,
+        threshold: u8,
+        governance_signer_bump: u8,
+    ) -> Result<()> {
+        Governance::check_signers(&signers, threshold)?;
+
+        self.governance.set_inner(Governance {
+            signers: signers.clone(),
+            threshold,
+            signer_bump: governance_signer_bump,
+            signers_seqno: 0,
+        });
+
+        emit!(EventCreateGovernance {
+            governance: self.governance.key(),
+            governance_signer: self.governance_signer.key(),
+            signers,
+            threshold
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+#[instruction(accounts: Vec<ProposalAccount>, data: Vec<u8>)]
+pub struct CreateProposal<'info> {
+    pub governance: Box<Account<'info, Governance>>,
+
+    #[account(
+        init,
+        space = Proposal::space(accounts.len(), data.len(), governance.signers.len()),
+        payer = rent_payer,
+        rent_exempt = enforce,
+    )]
+    pub proposal: Box<Account<'info, Proposal>>,
+
+    pub proposer: Signer<'info>,
+
+    #[account(
+        mut,
+        owner = system_program::ID
+    )]
+    pub rent_payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct EventCreateProposal {
+    pub governance: Pubkey,
+    pub proposal: Pubkey,
+    pub proposer: Pubkey,
+}
+
+impl<'info> CreateProposal<'info> {
+    pub fn process(&mut self, accounts: Vec<ProposalAccount>, data: Vec<u8>) -> Result<()> {
+        require!(
+            accounts.len() <= Proposal::MAX_ACCOUNTS_LEN && data.len() <= Proposal::MAX_DATA_LEN,
+            Errors::ProposalTooLarge
+        );
+
+        let proposer_index = self.governance.signer_index(&self.proposer.key())?;
+
+        let mut approvals = vec![false; self.governance.signers.len()];
+        approvals[proposer_index] = true;
+
+        self.proposal.set_inner(Proposal {
+            governance: self.governance.key(),
+            proposer: self.proposer.key(),
+            accounts,
+            data,
+            approvals,
+            signers_seqno: self.governance.signers_seqno,
+            executed: false,
+            cancelled: false,
+            rent_payer: self.rent_payer.key(),
+        });
+
+        emit!(EventCreateProposal {
+            governance: self.governance.key(),
+            proposal: self.proposal.key(),
+            proposer: self.proposer.key()
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ApproveProposal<'info> {
+    pub governance: Box<Account<'info, Governance>>,
+
+    #[account(
+        mut, 
+        has_one = governance @ Errors::GovernanceSignerNotMatch
+    )]
+    pub proposal: Box<Account<'info, Proposal>>,
+
+    pub signer: Signer<'info>,
+}
+
+#[event]
+pub struct EventApproveProposal {
+    pub proposal: Pubkey,
+    pub signer: Pubkey,
+}
+
+impl<'info> ApproveProposal<'info> {
+    pub fn process(&mut self) -> Result<()> {
+        require!(!self.proposal.executed, Errors::ProposalAlreadyExecuted);
+        require!(!self.proposal.cancelled, Errors::ProposalAlreadyCancelled);
+        require_eq!(
+            self.proposal.signers_seqno,
+            self.governance.signers_seqno,
+            Errors::ProposalSignersChanged
+        );
+
+        let signer_index = self.governance.signer_index(&self.signer.key())?;
+        self.proposal.approvals[signer_index] = true;
+
+        emit!(EventApproveProposal {
+            proposal: self.proposal.key(),
+            signer: self.signer.key()
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ExecuteProposal<'info> {
+    pub governance: Box<Account<'info, Governance>>,
+
+    /// CHECK: pda, signs the proposed instruction
+    #[account(
+        seeds = [
+            &governance.key().to_bytes(),
+            Governance::SIGNER_SEED,
+        ],
+        bump = governance.signer_bump,
+    )]
+    pub governance_signer: UncheckedAccount<'info>,
+
+    #[account(
+        mut, 
+        has_one = governance @ Errors::GovernanceSignerNotMatch
+    )]
+    pub proposal: Box<Account<'info, Proposal>>,
+}
+
+#[event]
+pub struct EventExecuteProposal {
+    pub governance: Pubkey,
+    pub proposal: Pubkey,
+}
+
+impl<'info> ExecuteProposal<'info> {
+    // remaining accounts: accounts of the proposed instruction
+    pub fn process(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(!self.proposal.executed, Errors::ProposalAlreadyExecuted);
+        require!(!self.proposal.cancelled, Errors::ProposalAlreadyCancelled);
+        require!(
+            self.proposal.approved(&self.governance),
+            Errors::ProposalNotApproved
+        );
+
+        self.proposal.executed = true;
+
+        // proposals can only call admin instructions of this program
+        let instruction = Instruction {
+            program_id: crate::ID,
+            accounts: self
+                .proposal
+                .accounts
+                .iter()
+                .map(|e| AccountMeta {
+                    pubkey: e.pubkey,
+                    is_signer: e.is_signer,
+                    is_writable: e.is_writable,
+                })
+                .collect(),
+            data: self.proposal.data.clone(),
+        };
+        invoke_signed(
+            &instruction,
+            remaining_accounts,
+            &[&[
+                &self.governance.key().to_bytes(),
+                Governance::SIGNER_SEED,
+                &[self.governance.signer_bump],
+            ]],
+        )?;
+
+        emit!(EventExecuteProposal {
+            governance: self.governance.key(),
+            proposal: self.proposal.key()
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct CancelProposal<'info> {
+    pub governance: Box<Account<'info, Governance>>,
+
+    #[account(
+        mut, 
+        has_one = governance @ Errors::GovernanceSignerNotMatch
+    )]
+    pub proposal: Box<Account<'info, Proposal>>,
+
+    pub signer: Signer<'info>,
+}
+
+#[event]
+pub struct EventCancelProposal {
+    pub proposal: Pubkey,
+    pub signer: Pubkey,
+}
+
+impl<'info> CancelProposal<'info> {
+    pub fn process(&mut self) -> Result<()> {
+        require!(!self.proposal.executed, Errors::ProposalAlreadyExecuted);
+        require!(!self.proposal.cancelled, Errors::ProposalAlreadyCancelled);
+
+        // any current signer can cancel
+        self.governance.signer_index(&self.signer.key())?;
+        self.proposal.cancelled = true;
+
+        emit!(EventCancelProposal {
+            proposal: self.proposal.key(),
+            signer: self.signer.key()
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct CloseProposal<'info> {
+    pub governance: Box<Account<'info, Governance>>,
+
+    #[account(
+        mut, 
+        has_one = governance @ Errors::GovernanceSignerNotMatch,
+        has_one = rent_payer @ Errors::RentPayerNotMatch,
+        close = rent_payer
+    )]
+    pub proposal: Box<Account<'info, Proposal>>,
+
+    #[account(mut)]
+    pub rent_payer: SystemAccount<'info>,
+}
+
+#[event]
+pub struct EventCloseProposal {
+    pub proposal: Pubkey,
+    pub rent_payer: Pubkey,
+}
+
+impl<'info> CloseProposal<'info> {
+    // permissionless, rent goes back to the rent payer
+    pub fn process(&mut self) -> Result<()> {
+        require!(
+            self.proposal.closable(&self.governance),
+            Errors::ProposalNotClosable
+        );
+
+        emit!(EventCloseProposal {
+            proposal: self.proposal.key(),
+            rent_payer: self.rent_payer.key()
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct SetGovernanceSigners<'info> {
+    #[account(mut)]
+    pub governance: Box<Account<'info, Governance>>,
+
+    // only reachable through an executed proposal
+    #[account(
+        seeds = [
+            &governance.key().to_bytes(),
+            Governance::SIGNER_SEED,
+        ],
+        bump = governance.signer_bump,
+    )]
+    pub governance_signer: Signer<'info>,
+}
+
+#[event]
+pub struct EventSetGovernanceSigners {
+    pub governance: Pubkey,
+    pub signers: Vec<Pubkey>,
+    pub threshold: u8,
+    pub signers_seqno: u32,
+}
+
+impl<'info> SetGovernanceSigners<'info> {
+    pub fn process(&mut self, signers: Vec<Pubkey>, threshold: u8) -> Result<()> {
+        Governance::check_signers(&signers, threshold)?;
+
+        self.governance.signers = signers.clone();
+        self.governance.threshold = threshold;
+        self.governance.signers_seqno += 1;
+
+        emit!(EventSetGovernanceSigners {
+            governance: self.governance.key(),
+            signers,
+            threshold,
+            signers_seqno: self.governance.signers_seqno
+        });
+        Ok(())
+    }
+}
@@ -31,6 +31,10 @@ impl<'info> InitializeStack<'info> {
             stack_fee_commission: Stack::DEFAULT_STACK_FEE_COMMISSION,
             stake_managers_len_limit: Stack::DEFAULT_STAKE_MANAGERS_LEN_LIMIT,
             entrusted_stake_managers: vec![],
+            max_deposit_fee_bps: Stack::DEFAULT_MAX_DEPOSIT_FEE_BPS,
+            max_unstake_fee_bps: Stack::DEFAULT_MAX_UNSTAKE_FEE_BPS,
+            max_platform_fee_commission: Stack::DEFAULT_MAX_PLATFORM_FEE_COMMISSION,
+            stack_fee_recipient: self.admin.key(),
         });
 
         Ok(())
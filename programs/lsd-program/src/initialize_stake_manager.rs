@@ -5,7 +5,10 @@ use crate::StackFeeAccount;
 pub use crate::StakeManager;
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::system_program;
-use anchor_spl::{associated_token::AssociatedToken, token::Mint};
+use anchor_spl::{
+    associated_token::AssociatedToken,
+    token::{Mint, Token, TokenAccount},
+};
 
 #[derive(Accounts)]
 pub struct InitializeStakeManager<'info> {
@@ -39,6 +42,23 @@ pub struct InitializeStakeManager<'info> {
     )]
     pub stack_fee_account: Box<Account<'info, StackFeeAccount>>,
 
+    #[account(
+        init,
+        payer = rent_payer,
+        associated_token::mint = lsd_token_mint,
+        associated_token::authority = stack_fee_account,
+    )]
+    pub stack_fee_escrow: Box<Account<'info, TokenAccount>>,
+
+    // holds referral fee, insurance buffer and crank budget
+    #[account(
+        init_if_needed,
+        payer = rent_payer,
+        associated_token::mint = lsd_token_mint,
+        associated_token::authority = stake_pool,
+    )]
+    pub referral_fee_escrow: Box<Account<'info, TokenAccount>>,
+
     pub lsd_token_mint: Box<Account<'info, Mint>>,
 
     /// CHECK: todo
@@ -53,6 +73,7 @@ pub struct InitializeStakeManager<'info> {
     pub admin: Signer<'info>,
 
     pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
     pub clock: Sysvar<'info, Clock>,
     pub rent: Sysvar<'info, Rent>,
@@ -112,6 +133,44 @@ impl<'info> InitializeStakeManager<'info> {
                 new_active: 0,
                 pending_stake_accounts: vec![],
             },
+            unstake_requested: 0,
+            unstake_fulfilled: 0,
+            unstake_claimed: 0,
+            referral_fee_commission: 0,
+            referral_active: 0,
+            referral_fee_per_active: 0,
+            platform_fee_recipient: self.admin.key(),
+            deposit_fee_bps: 0,
+            unstake_fee_bps: 0,
+            pending_fee: 0,
+            config_timelock_epochs: StakeManager::DEFAULT_CONFIG_TIMELOCK_EPOCHS,
+            pending_configs: vec![],
+            fee_manager: self.admin.key(),
+            validator_manager: self.admin.key(),
+            pauser: self.admin.key(),
+            param_manager: self.admin.key(),
+            paused: false,
+            deposit_authority: None,
+            max_active: 0,
+            epoch_deposit_cap: 0,
+            epoch_deposit_epoch: 0,
+            epoch_deposited: 0,
+            insurance_fee_commission: 0,
+            insurance_buffer: 0,
+            rate_up_limit: 0,
+            rate_down_limit: 0,
+            tracked_pool_balance: 0,
+            excess_reward: 0,
+            total_unstake_pending: 0,
+            total_unstake_claimable: 0,
+            unbonding_splits: vec![],
+            crank_fee_commission: 0,
+            crank_reward: 0,
+            crank_budget: 0,
+            rent_as_yield: false,
+            rent_payers: vec![],
+            era_epochs: 0,
+            version: StakeManager::CURRENT_VERSION,
         });
 
         self.stack_fee_account.set_inner(StackFeeAccount {
@@ -2,7 +2,12 @@ use anchor_lang::{prelude::*, Bumps};
 
 pub mod admin_stack;
 pub mod admin_stake_manager;
+pub mod apply_pending_config;
+pub mod cranker;
+pub mod deposit_allowlist;
 pub mod era_bond;
+pub mod era_claim_excess;
+pub mod era_finalize;
 pub mod era_merge;
 pub mod era_new;
 pub mod era_skip_bond;
@@ -11,17 +16,26 @@ pub mod era_update_active;
 pub mod era_update_rate;
 pub mod era_withdraw;
 pub mod errors;
+pub mod governance;
 pub mod initialize_stack;
 pub mod initialize_stake_manager;
 pub mod redelegate;
+pub mod referrer;
 pub mod staker_stake;
+pub mod staker_transfer_unstake_ticket;
 pub mod staker_unstake;
 pub mod staker_withdraw;
 pub mod states;
+pub mod verify_invariants;
 
 pub use crate::admin_stack::*;
 pub use crate::admin_stake_manager::*;
+pub use crate::apply_pending_config::*;
+pub use crate::cranker::*;
+pub use crate::deposit_allowlist::*;
 pub use crate::era_bond::*;
+pub use crate::era_claim_excess::*;
+pub use crate::era_finalize::*;
 pub use crate::era_merge::*;
 pub use crate::era_new::*;
 pub use crate::era_skip_bond::*;
@@ -30,21 +44,31 @@ pub use crate::era_update_active::*;
 pub use crate::era_update_rate::*;
 pub use crate::era_withdraw::*;
 pub use crate::errors::Errors;
+pub use crate::governance::*;
 pub use crate::initialize_stack::*;
 pub use crate::initialize_stake_manager::*;
 pub use crate::redelegate::*;
+pub use crate::referrer::*;
 pub use crate::staker_stake::*;
+pub use crate::staker_transfer_unstake_ticket::*;
 pub use crate::staker_unstake::*;
 pub use crate::staker_withdraw::*;
 pub use crate::states::*;
+pub use crate::verify_invariants::*;
 
 declare_id!("795MBfkwwtAX4fWiFqZcJK8D91P9tqqtiSRrSNhBvGzq");
 
-fn check_context<T: Bumps>(ctx: &Context<T>) -> Result<()> {
+fn check_program_id<T: Bumps>(ctx: &Context<T>) -> Result<()> {
     if !check_id(ctx.program_id) {
         return err!(Errors::ProgramIdNotMatch);
     }
 
+    Ok(())
+}
+
+fn check_context<T: Bumps>(ctx: &Context<T>) -> Result<()> {
+    check_program_id(ctx)?;
+
     if !ctx.remaining_accounts.is_empty() {
         return err!(Errors::RemainingAccountsNotMatch);
     }
@@ -76,6 +100,79 @@ pub mod lsd_program {
         Ok(())
     }
 
+    // governance
+
+    pub fn create_governance(
+        ctx: Context<CreateGovernance>,
+        signers: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts
+            .process(signers, threshold, ctx.bumps.governance_signer)?;
+
+        Ok(())
+    }
+
+    pub fn create_proposal(
+        ctx: Context<CreateProposal>,
+        accounts: Vec<ProposalAccount>,
+        data: Vec<u8>,
+    ) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process(accounts, data)?;
+
+        Ok(())
+    }
+
+    pub fn approve_proposal(ctx: Context<ApproveProposal>) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process()?;
+
+        Ok(())
+    }
+
+    pub fn execute_proposal<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ExecuteProposal<'info>>,
+    ) -> Result<()> {
+        check_program_id(&ctx)?;
+
+        ctx.accounts.process(ctx.remaining_accounts)?;
+
+        Ok(())
+    }
+
+    pub fn cancel_proposal(ctx: Context<CancelProposal>) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process()?;
+
+        Ok(())
+    }
+
+    pub fn close_proposal(ctx: Context<CloseProposal>) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process()?;
+
+        Ok(())
+    }
+
+    pub fn set_governance_signers(
+        ctx: Context<SetGovernanceSigners>,
+        signers: Vec<Pubkey>,
+        threshold: u8,
+    ) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process(signers, threshold)?;
+
+        Ok(())
+    }
+
     // admin of stack
 
     pub fn transfer_stack_admin(ctx: Context<TransferStackAdmin>, new_admin: Pubkey) -> Result<()> {
@@ -86,6 +183,17 @@ pub mod lsd_program {
         Ok(())
     }
 
+    pub fn set_stack_fee_recipient(
+        ctx: Context<SetStackFeeRecipient>,
+        stack_fee_recipient: Pubkey,
+    ) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process(stack_fee_recipient)?;
+
+        Ok(())
+    }
+
     pub fn set_stack_fee_commission(
         ctx: Context<SetStackFeeCommission>,
         stack_fee_commission: u64,
@@ -97,6 +205,17 @@ pub mod lsd_program {
         Ok(())
     }
 
+    pub fn set_max_platform_fee_commission(
+        ctx: Context<SetMaxPlatformFeeCommission>,
+        max_platform_fee_commission: u64,
+    ) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process(max_platform_fee_commission)?;
+
+        Ok(())
+    }
+
     pub fn set_platform_stack_fee_commission(
         ctx: Context<SetPlatformStackFeeCommission>,
         stack_fee_commission: u64,
@@ -108,6 +227,30 @@ pub mod lsd_program {
         Ok(())
     }
 
+    pub fn set_max_fee_bps(
+        ctx: Context<SetMaxFeeBps>,
+        max_deposit_fee_bps: u64,
+        max_unstake_fee_bps: u64,
+    ) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts
+            .process(max_deposit_fee_bps, max_unstake_fee_bps)?;
+
+        Ok(())
+    }
+
+    pub fn set_config_timelock(
+        ctx: Context<SetConfigTimelock>,
+        config_timelock_epochs: u64,
+    ) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process(config_timelock_epochs)?;
+
+        Ok(())
+    }
+
     pub fn add_entrusted_stake_manager(
         ctx: Context<AddEntrustedStakeManager>,
         stake_manager: Pubkey,
@@ -130,6 +273,22 @@ pub mod lsd_program {
         Ok(())
     }
 
+    pub fn claim_stack_fee(ctx: Context<ClaimStackFee>, claim_amount: u64) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process(claim_amount)?;
+
+        Ok(())
+    }
+
+    pub fn init_stack_fee_escrow(ctx: Context<InitStackFeeEscrow>) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process(ctx.bumps.stack_fee_account)?;
+
+        Ok(())
+    }
+
     // admin of stake manager
 
     pub fn transfer_stake_manager_admin(
@@ -151,6 +310,33 @@ pub mod lsd_program {
         Ok(())
     }
 
+    pub fn set_role(ctx: Context<SetRole>, role: Role, new_key: Pubkey) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process(role, new_key)?;
+
+        Ok(())
+    }
+
+    pub fn set_paused(ctx: Context<SetPaused>, paused: bool) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process(paused)?;
+
+        Ok(())
+    }
+
+    pub fn set_deposit_authority(
+        ctx: Context<SetDepositAuthority>,
+        deposit_authority: Option<Pubkey>,
+    ) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process(deposit_authority)?;
+
+        Ok(())
+    }
+
     pub fn set_min_stake_amount(ctx: Context<SetMinStakeAmount>, amount: u64) -> Result<()> {
         check_context(&ctx)?;
 
@@ -159,6 +345,18 @@ pub mod lsd_program {
         Ok(())
     }
 
+    pub fn set_deposit_caps(
+        ctx: Context<SetDepositCaps>,
+        max_active: u64,
+        epoch_deposit_cap: u64,
+    ) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process(max_active, epoch_deposit_cap)?;
+
+        Ok(())
+    }
+
     pub fn set_unbonding_duration(ctx: Context<SetUnbondingDuration>, duration: u64) -> Result<()> {
         check_context(&ctx)?;
 
@@ -167,6 +365,44 @@ pub mod lsd_program {
         Ok(())
     }
 
+    pub fn set_insurance_fee_commission(
+        ctx: Context<SetInsuranceFeeCommission>,
+        insurance_fee_commission: u64,
+    ) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process(insurance_fee_commission)?;
+
+        Ok(())
+    }
+
+    pub fn set_crank_fee_commission(
+        ctx: Context<SetCrankFeeCommission>,
+        crank_fee_commission: u64,
+    ) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process(crank_fee_commission)?;
+
+        Ok(())
+    }
+
+    pub fn set_crank_reward(ctx: Context<SetCrankReward>, crank_reward: u64) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process(crank_reward)?;
+
+        Ok(())
+    }
+
+    pub fn set_rent_as_yield(ctx: Context<SetRentAsYield>, rent_as_yield: bool) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process(rent_as_yield)?;
+
+        Ok(())
+    }
+
     pub fn set_rate_change_limit(
         ctx: Context<SetRateChangeLimit>,
         rate_change_limit: u64,
@@ -189,6 +425,75 @@ pub mod lsd_program {
         Ok(())
     }
 
+    pub fn set_platform_fee_recipient(
+        ctx: Context<SetPlatformFeeRecipient>,
+        platform_fee_recipient: Pubkey,
+    ) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process(platform_fee_recipient)?;
+
+        Ok(())
+    }
+
+    pub fn set_deposit_fee_bps(ctx: Context<SetDepositFeeBps>, deposit_fee_bps: u64) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process(deposit_fee_bps)?;
+
+        Ok(())
+    }
+
+    pub fn set_unstake_fee_bps(ctx: Context<SetUnstakeFeeBps>, unstake_fee_bps: u64) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process(unstake_fee_bps)?;
+
+        Ok(())
+    }
+
+    pub fn set_referral_fee_commission(
+        ctx: Context<SetReferralFeeCommission>,
+        referral_fee_commission: u64,
+    ) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process(referral_fee_commission)?;
+
+        Ok(())
+    }
+
+    pub fn set_rate_direction_limits(
+        ctx: Context<SetRateDirectionLimits>,
+        rate_up_limit: u64,
+        rate_down_limit: u64,
+    ) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process(rate_up_limit, rate_down_limit)?;
+
+        Ok(())
+    }
+
+    pub fn cancel_pending_config(
+        ctx: Context<CancelPendingConfig>,
+        param: ConfigParam,
+    ) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process(param)?;
+
+        Ok(())
+    }
+
+    pub fn apply_pending_config(ctx: Context<ApplyPendingConfig>) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process()?;
+
+        Ok(())
+    }
+
     pub fn add_validator(ctx: Context<AddValidator>, new_validator: Pubkey) -> Result<()> {
         check_context(&ctx)?;
 
@@ -213,6 +518,25 @@ pub mod lsd_program {
         Ok(())
     }
 
+    pub fn migrate_stake_manager<'info>(
+        ctx: Context<'_, '_, 'info, 'info, MigrateStakeManager<'info>>,
+        new_size: u32,
+    ) -> Result<()> {
+        check_program_id(&ctx)?;
+
+        ctx.accounts.process(new_size, ctx.remaining_accounts)?;
+
+        Ok(())
+    }
+
+    pub fn migrate_unstake_account(ctx: Context<MigrateUnstakeAccount>) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process()?;
+
+        Ok(())
+    }
+
     // balancer
 
     pub fn redelegate(ctx: Context<Redelegate>, redelegate_amount: u64) -> Result<()> {
@@ -223,20 +547,86 @@ pub mod lsd_program {
         Ok(())
     }
 
+    // deposit authority
+
+    pub fn add_deposit_allowlist(
+        ctx: Context<AddDepositAllowlist>,
+        depositor: Pubkey,
+    ) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts
+            .process(depositor, ctx.bumps.deposit_allowlist_account)?;
+
+        Ok(())
+    }
+
+    pub fn remove_deposit_allowlist(ctx: Context<RemoveDepositAllowlist>) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process()?;
+
+        Ok(())
+    }
+
+    // referrer
+
+    pub fn register_referrer(ctx: Context<RegisterReferrer>) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process(ctx.bumps.referrer_account)?;
+
+        Ok(())
+    }
+
+    pub fn claim_referral_fee(ctx: Context<ClaimReferralFee>) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process()?;
+
+        Ok(())
+    }
+
+    pub fn sync_referred(ctx: Context<SyncReferred>) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process()?;
+
+        Ok(())
+    }
+
     // staker
 
     pub fn stake(ctx: Context<Stake>, stake_amount: u64) -> Result<()> {
         check_context(&ctx)?;
 
-        ctx.accounts.process(stake_amount)?;
+        ctx.accounts
+            .process(stake_amount, ctx.bumps.referred_account)?;
+
+        Ok(())
+    }
+
+    pub fn unstake(
+        ctx: Context<Unstake>,
+        unstake_amount: u64,
+        recipient: Option<Pubkey>,
+    ) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts
+            .process(unstake_amount, recipient, ctx.bumps.unstake_nonce_account)?;
 
         Ok(())
     }
 
-    pub fn unstake(ctx: Context<Unstake>, unstake_amount: u64) -> Result<()> {
+    pub fn transfer_unstake_ticket(
+        ctx: Context<TransferUnstakeTicket>,
+        new_recipient: Pubkey,
+    ) -> Result<()> {
         check_context(&ctx)?;
 
-        ctx.accounts.process(unstake_amount)?;
+        ctx.accounts
+            .process(new_recipient, ctx.bumps.new_unstake_nonce_account)?;
 
         Ok(())
     }
@@ -249,12 +639,60 @@ pub mod lsd_program {
         Ok(())
     }
 
+    pub fn get_unstake_claim_epoch(ctx: Context<GetUnstakeClaimEpoch>) -> Result<u64> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process()
+    }
+
+    pub fn get_unstake_queue_ahead(ctx: Context<GetUnstakeQueueAhead>) -> Result<u64> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process()
+    }
+
+    pub fn withdraw_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, WithdrawBatch<'info>>,
+    ) -> Result<()> {
+        check_program_id(&ctx)?;
+
+        ctx.accounts.process(ctx.remaining_accounts)?;
+
+        Ok(())
+    }
+
+    // cranker
+
+    pub fn register_cranker(ctx: Context<RegisterCranker>) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process(ctx.bumps.cranker_account)?;
+
+        Ok(())
+    }
+
+    pub fn claim_crank_reward(ctx: Context<ClaimCrankReward>) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process()?;
+
+        Ok(())
+    }
+
     // era
 
     pub fn era_new(ctx: Context<EraNew>) -> Result<()> {
         check_context(&ctx)?;
 
-        ctx.accounts.process()?;
+        ctx.accounts.process(false)?;
+
+        Ok(())
+    }
+
+    pub fn era_new_catch_up(ctx: Context<EraNew>) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process(true)?;
 
         Ok(())
     }
@@ -291,6 +729,16 @@ pub mod lsd_program {
         Ok(())
     }
 
+    pub fn era_update_active_batch<'info>(
+        ctx: Context<'_, '_, 'info, 'info, EraUpdateActiveBatch<'info>>,
+    ) -> Result<()> {
+        check_program_id(&ctx)?;
+
+        ctx.accounts.process(ctx.remaining_accounts)?;
+
+        Ok(())
+    }
+
     pub fn era_update_rate(ctx: Context<EraUpdateRate>) -> Result<()> {
         check_context(&ctx)?;
 
@@ -299,6 +747,27 @@ pub mod lsd_program {
         Ok(())
     }
 
+    pub fn era_finalize<'info>(
+        ctx: Context<'_, '_, 'info, 'info, EraFinalize<'info>>,
+    ) -> Result<()> {
+        check_program_id(&ctx)?;
+
+        ctx.accounts.process(ctx.remaining_accounts)?;
+
+        Ok(())
+    }
+
+    pub fn force_era_update_rate(
+        ctx: Context<ForceEraUpdateRate>,
+        expected_rate: u64,
+    ) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process(expected_rate)?;
+
+        Ok(())
+    }
+
     pub fn era_merge(ctx: Context<EraMerge>) -> Result<()> {
         check_context(&ctx)?;
 
@@ -314,4 +783,32 @@ pub mod lsd_program {
 
         Ok(())
     }
+
+    pub fn era_claim_stake_excess(ctx: Context<EraClaimStakeExcess>) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process()?;
+
+        Ok(())
+    }
+
+    pub fn era_claim_pool_excess(ctx: Context<EraClaimPoolExcess>) -> Result<()> {
+        check_context(&ctx)?;
+
+        ctx.accounts.process()?;
+
+        Ok(())
+    }
+
+    // monitor
+
+    pub fn verify_invariants<'info>(
+        ctx: Context<'_, '_, 'info, 'info, VerifyInvariants<'info>>,
+    ) -> Result<()> {
+        check_program_id(&ctx)?;
+
+        ctx.accounts.process(ctx.remaining_accounts)?;
+
+        Ok(())
+    }
 }
@@ -135,7 +135,23 @@ impl<'info> Redelegate<'info> {
 
         require_gte!(delegation.stake, redelegate_amount, Errors::AmountUnmatch);
 
+        let account_len = self.stake_manager.to_account_info().data_len();
+        self.stake_manager.add_rent_payer(
+            self.to_stake_account.key(),
+            self.rent_payer.key(),
+            self.to_stake_account.get_lamports(),
+            account_len,
+        )?;
+
         let will_redelegate_from_stake_account = if redelegate_amount < delegation.stake {
+            let account_len = self.stake_manager.to_account_info().data_len();
+            self.stake_manager.add_rent_payer(
+                self.split_stake_account.key(),
+                self.rent_payer.key(),
+                self.split_stake_account.get_lamports(),
+                account_len,
+            )?;
+
             // split
             let split_instruction = stake::instruction::split(
                 self.from_stake_account.to_account_info().key,
@@ -0,0 +1,208 @@
+use crate::{Errors, ReferredAccount, ReferrerAccount, StakeManager};
+use anchor_lang::{prelude::*, solana_program::system_program};
+use anchor_spl::token::{transfer, Token, TokenAccount, Transfer};
+
+#[derive(Accounts)]
+pub struct RegisterReferrer<'info> {
+    pub stake_manager: Box<Account<'info, StakeManager>>,
+
+    #[account(
+        init,
+        space = 8 + std::mem::size_of::<ReferrerAccount>(),
+        payer = rent_payer,
+        rent_exempt = enforce,
+        seeds = [
+            &stake_manager.key().to_bytes(),
+            &referrer.key().to_bytes(),
+            ReferrerAccount::SEED,
+        ],
+        bump,
+    )]
+    pub referrer_account: Box<Account<'info, ReferrerAccount>>,
+
+    pub referrer: Signer<'info>,
+
+    #[account(
+        mut,
+        owner = system_program::ID
+    )]
+    pub rent_payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct EventRegisterReferrer {
+    pub stake_manager: Pubkey,
+    pub referrer: Pubkey,
+    pub referrer_account: Pubkey,
+}
+
+impl<'info> RegisterReferrer<'info> {
+    pub fn process(&mut self, referrer_account_bump: u8) -> Result<()> {
+        self.referrer_account.set_inner(ReferrerAccount {
+            stake_manager: self.stake_manager.key(),
+            referrer: self.referrer.key(),
+            bump: referrer_account_bump,
+            total_deposit: 0,
+            active: 0,
+            fee_debt: 0,
+            unclaimed_fee: 0,
+        });
+
+        emit!(EventRegisterReferrer {
+            stake_manager: self.stake_manager.key(),
+            referrer: self.referrer.key(),
+            referrer_account: self.referrer_account.key(),
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ClaimReferralFee<'info> {
+    pub stake_manager: Box<Account<'info, StakeManager>>,
+
+    #[account(
+        seeds = [
+            &stake_manager.key().to_bytes(),
+            StakeManager::POOL_SEED,
+        ],
+        bump = stake_manager.pool_seed_bump
+    )]
+    pub stake_pool: SystemAccount<'info>,
+
+    #[account(
+        mut,
+        has_one = referrer @ Errors::ReferrerNotMatch,
+        seeds = [
+            &stake_manager.key().to_bytes(),
+            &referrer.key().to_bytes(),
+            ReferrerAccount::SEED,
+        ],
+        bump = referrer_account.bump,
+    )]
+    pub referrer_account: Box<Account<'info, ReferrerAccount>>,
+
+    pub referrer: Signer<'info>,
+
+    #[account(
+        mut,
+        associated_token::mint = stake_manager.lsd_token_mint,
+        associated_token::authority = stake_pool,
+    )]
+    pub referral_fee_escrow: Box<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = stake_manager.lsd_token_mint
+    )]
+    pub claim_to: Box<Account<'info, TokenAccount>>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[event]
+pub struct EventClaimReferralFee {
+    pub referrer: Pubkey,
+    pub claim_to: Pubkey,
+    pub claim_amount: u64,
+}
+
+impl<'info> ClaimReferralFee<'info> {
+    pub fn process(&mut self) -> Result<()> {
+        self.stake_manager
+            .settle_referral_fee(&mut self.referrer_account)?;
+
+        let claim_amount = self.referrer_account.unclaimed_fee;
+        require_gt!(claim_amount, 0, Errors::AmountUnmatch);
+
+        self.referrer_account.unclaimed_fee = 0;
+
+        transfer(
+            CpiContext::new_with_signer(
+                self.token_program.to_account_info(),
+                Transfer {
+                    from: self.referral_fee_escrow.to_account_info(),
+                    to: self.claim_to.to_account_info(),
+                    authority: self.stake_pool.to_account_info(),
+                },
+                &[&[
+                    &self.stake_manager.key().to_bytes(),
+                    StakeManager::POOL_SEED,
+                    &[self.stake_manager.pool_seed_bump],
+                ]],
+            ),
+            claim_amount,
+        )?;
+
+        emit!(EventClaimReferralFee {
+            referrer: self.referrer.key(),
+            claim_to: self.claim_to.key(),
+            claim_amount
+        });
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct SyncReferred<'info> {
+    #[account(mut)]
+    pub stake_manager: Box<Account<'info, StakeManager>>,
+
+    #[account(
+        mut,
+        has_one = token_account @ Errors::ReferredAccountNotMatch,
+        seeds = [
+            &stake_manager.key().to_bytes(),
+            &token_account.key().to_bytes(),
+            ReferredAccount::SEED,
+        ],
+        bump = referred_account.bump,
+    )]
+    pub referred_account: Box<Account<'info, ReferredAccount>>,
+
+    #[account(
+        mut,
+        address = Pubkey::find_program_address(
+            &[
+                &stake_manager.key().to_bytes(),
+                &referred_account.referrer.to_bytes(),
+                ReferrerAccount::SEED,
+            ],
+            &crate::ID,
+        ).0 @ Errors::ReferrerNotMatch,
+    )]
+    pub referrer_account: Box<Account<'info, ReferrerAccount>>,
+
+    pub token_account: Box<Account<'info, TokenAccount>>,
+}
+
+#[event]
+pub struct EventSyncReferred {
+    pub referrer: Pubkey,
+    pub token_account: Pubkey,
+    pub old_amount: u64,
+    pub new_amount: u64,
+}
+
+impl<'info> SyncReferred<'info> {
+    // permissionless, lsd token transferred out of the token account stops counting
+    pub fn process(&mut self) -> Result<()> {
+        let old_amount = self.referred_account.amount;
+
+        self.stake_manager.sync_referral_active(
+            &mut self.referrer_account,
+            &mut self.referred_account,
+            self.token_account.amount,
+        )?;
+
+        emit!(EventSyncReferred {
+            referrer: self.referrer_account.referrer,
+            token_account: self.token_account.key(),
+            old_amount,
+            new_amount: self.referred_account.amount
+        });
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{Errors, StakeManager};
+use crate::{DepositAllowlistAccount, Errors, ReferredAccount, ReferrerAccount, StakeManager};
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::system_program;
 use anchor_lang::system_program::{transfer, Transfer};
@@ -38,6 +38,45 @@ pub struct Stake<'info> {
     )]
     pub mint_to: Box<Account<'info, TokenAccount>>,
 
+    #[account(
+        mut,
+        seeds = [
+            &stake_manager.key().to_bytes(),
+            &referrer_account.referrer.to_bytes(),
+            ReferrerAccount::SEED,
+        ],
+        bump = referrer_account.bump,
+    )]
+    pub referrer_account: Option<Box<Account<'info, ReferrerAccount>>>,
+
+    // required with referrer_account
+    #[account(
+        init_if_needed,
+        space = 8 + std::mem::size_of::<ReferredAccount>(),
+        payer = from,
+        rent_exempt = enforce,
+        seeds = [
+            &stake_manager.key().to_bytes(),
+            &mint_to.key().to_bytes(),
+            ReferredAccount::SEED,
+        ],
+        bump,
+    )]
+    pub referred_account: Option<Box<Account<'info, ReferredAccount>>>,
+
+    pub deposit_authority: Option<Signer<'info>>,
+
+    #[account(
+        seeds = [
+            &stake_manager.key().to_bytes(),
+            &from.key().to_bytes(),
+            DepositAllowlistAccount::SEED,
+        ],
+        bump = deposit_allowlist_account.bump,
+    )]
+    pub deposit_allowlist_account: Option<Box<Account<'info, DepositAllowlistAccount>>>,
+
+    pub clock: Sysvar<'info, Clock>,
     pub system_program: Program<'info, System>,
     pub token_program: Program<'info, Token>,
 }
@@ -49,10 +88,31 @@ pub struct EventStake {
     pub mint_to: Pubkey,
     pub stake_amount: u64,
     pub lsd_token_amount: u64,
+    pub deposit_fee: u64,
+    pub referrer: Option<Pubkey>,
+    pub referrer_total_deposit: u64,
 }
 
 impl<'info> Stake<'info> {
-    pub fn process(&mut self, stake_amount: u64) -> Result<()> {
+    pub fn process(&mut self, stake_amount: u64, referred_account_bump: u8) -> Result<()> {
+        require!(!self.stake_manager.paused, Errors::StakeManagerPaused);
+        require_eq!(
+            self.stake_manager.version,
+            StakeManager::CURRENT_VERSION,
+            Errors::StakeManagerNotMigrated
+        );
+
+        // permissioned pool: deposit authority co-signs or the depositor is allowlisted
+        if let Some(deposit_authority) = self.stake_manager.deposit_authority {
+            let authority_signed = self
+                .deposit_authority
+                .as_ref()
+                .is_some_and(|e| e.key() == deposit_authority);
+            require!(
+                authority_signed || self.deposit_allowlist_account.is_some(),
+                Errors::DepositNotAllowed
+            );
+        }
         require_gte!(
             stake_amount,
             self.stake_manager.min_stake_amount,
@@ -62,10 +122,61 @@ impl<'info> Stake<'info> {
         let user_balance = self.from.lamports();
         require_gte!(user_balance, stake_amount, Errors::BalanceNotEnough);
 
-        let lsd_token_amount = self.stake_manager.calc_lsd_token_amount(stake_amount)?;
+        let lsd_token_amount_raw = self.stake_manager.calc_lsd_token_amount(stake_amount)?;
+        let deposit_fee = self
+            .stake_manager
+            .calc_bps_fee(lsd_token_amount_raw, self.stake_manager.deposit_fee_bps)?;
+        let lsd_token_amount = lsd_token_amount_raw - deposit_fee;
+
+        // deposit fee is minted with the next era's fees
+        self.stake_manager.pending_fee += deposit_fee;
 
+        self.stake_manager
+            .add_deposit(stake_amount, self.clock.epoch)?;
         self.stake_manager.era_bond += stake_amount;
         self.stake_manager.active += stake_amount;
+        self.stake_manager.tracked_pool_balance += stake_amount;
+
+        let (referrer, referrer_total_deposit) = match self.referrer_account.as_mut() {
+            Some(referrer_account) => {
+                let referred_account = self
+                    .referred_account
+                    .as_mut()
+                    .ok_or_else(|| error!(Errors::ReferredAccountNotMatch))?;
+                if referred_account.token_account == Pubkey::default() {
+                    referred_account.set_inner(ReferredAccount {
+                        stake_manager: self.stake_manager.key(),
+                        token_account: self.mint_to.key(),
+                        referrer: referrer_account.referrer,
+                        bump: referred_account_bump,
+                        amount: 0,
+                    });
+                }
+                // a token account keeps its first referrer
+                require_keys_eq!(
+                    referred_account.referrer,
+                    referrer_account.referrer,
+                    Errors::ReferrerNotMatch
+                );
+
+                self.stake_manager.sync_referral_active(
+                    referrer_account,
+                    referred_account,
+                    self.mint_to.amount,
+                )?;
+                self.stake_manager.add_referral_active(
+                    referrer_account,
+                    referred_account,
+                    stake_amount,
+                    lsd_token_amount,
+                )?;
+                (
+                    Some(referrer_account.referrer),
+                    referrer_account.total_deposit,
+                )
+            }
+            None => (None, 0),
+        };
 
         // transfer lamports to the pool
         transfer(
@@ -102,7 +213,10 @@ impl<'info> Stake<'info> {
             staker: self.from.key(),
             mint_to: self.mint_to.key(),
             stake_amount,
-            lsd_token_amount
+            lsd_token_amount,
+            deposit_fee,
+            referrer,
+            referrer_total_deposit
         });
         Ok(())
     }
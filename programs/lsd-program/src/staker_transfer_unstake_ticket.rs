@@ -0,0 +1,103 @@
+use crate::{Errors, UnstakeAccount, UnstakeNonceAccount};
+use anchor_lang::{prelude::*, solana_program::system_program};
+
+#[derive(Accounts)]
+#[instruction(new_recipient: Pubkey)]
+pub struct TransferUnstakeTicket<'info> {
+    #[account(
+        mut,
+        has_one = recipient @ Errors::UnstakeRecipientNotMatch,
+        close = ticket_rent_payer
+    )]
+    pub unstake_account: Box<Account<'info, UnstakeAccount>>,
+
+    pub recipient: Signer<'info>,
+
+    #[account(
+        mut,
+        address = unstake_account.rent_payer @ Errors::RentPayerNotMatch
+    )]
+    pub ticket_rent_payer: SystemAccount<'info>,
+
+    // keyed on the current recipient so transfers can't move the new recipient's nonce
+    #[account(
+        init_if_needed,
+        space = 8 + std::mem::size_of::<UnstakeNonceAccount>(),
+        payer = rent_payer,
+        rent_exempt = enforce,
+        seeds = [
+            &unstake_account.stake_manager.to_bytes(),
+            &recipient.key().to_bytes(),
+            UnstakeNonceAccount::SEED,
+        ],
+        bump,
+    )]
+    pub new_unstake_nonce_account: Box<Account<'info, UnstakeNonceAccount>>,
+
+    // the ticket moves to a new address derived from the current recipient
+    #[account(
+        init,
+        space = 8 + std::mem::size_of::<UnstakeAccount>(),
+        payer = rent_payer,
+        rent_exempt = enforce,
+        seeds = [
+            &unstake_account.stake_manager.to_bytes(),
+            &recipient.key().to_bytes(),
+            new_unstake_nonce_account.nonce.to_le_bytes().as_ref(),
+        ],
+        bump,
+    )]
+    pub new_unstake_account: Box<Account<'info, UnstakeAccount>>,
+
+    #[account(
+        mut,
+        owner = system_program::ID
+    )]
+    pub rent_payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct EventTransferUnstakeTicket {
+    pub unstake_account: Pubkey,
+    pub new_unstake_account: Pubkey,
+    pub old_recipient: Pubkey,
+    pub new_recipient: Pubkey,
+    pub amount: u64,
+    pub nonce: u64,
+}
+
+impl<'info> TransferUnstakeTicket<'info> {
+    pub fn process(
+        &mut self,
+        new_recipient: Pubkey,
+        new_unstake_nonce_account_bump: u8,
+    ) -> Result<()> {
+        require_gt!(
+            self.unstake_account.amount,
+            0,
+            Errors::UnstakeAccountAmountZero
+        );
+
+        self.new_unstake_account.set_inner(UnstakeAccount {
+            recipient: new_recipient,
+            rent_payer: self.rent_payer.key(),
+            ..(**self.unstake_account).clone()
+        });
+
+        let nonce = self.new_unstake_nonce_account.nonce;
+        self.new_unstake_nonce_account.bump = new_unstake_nonce_account_bump;
+        self.new_unstake_nonce_account.nonce += 1;
+
+        emit!(EventTransferUnstakeTicket {
+            unstake_account: self.unstake_account.key(),
+            new_unstake_account: self.new_unstake_account.key(),
+            old_recipient: self.recipient.key(),
+            new_recipient,
+            amount: self.unstake_account.amount,
+            nonce
+        });
+        Ok(())
+    }
+}
@@ -1,4 +1,6 @@
-use crate::{Errors, StakeManager, UnstakeAccount};
+use crate::{
+    Errors, ReferredAccount, ReferrerAccount, StakeManager, UnstakeAccount, UnstakeNonceAccount,
+};
 use anchor_lang::{prelude::*, solana_program::system_program};
 use anchor_spl::token::{burn, Burn, Mint, Token, TokenAccount};
 
@@ -21,14 +23,59 @@ pub struct Unstake<'info> {
 
     pub burn_lsd_token_authority: Signer<'info>,
 
+    // keyed on the owner so unstakes redirected to a recipient can't move its nonce
+    #[account(
+        init_if_needed,
+        space = 8 + std::mem::size_of::<UnstakeNonceAccount>(),
+        payer = rent_payer,
+        rent_exempt = enforce,
+        seeds = [
+            &stake_manager.key().to_bytes(),
+            &burn_lsd_token_from.owner.to_bytes(),
+            UnstakeNonceAccount::SEED,
+        ],
+        bump,
+    )]
+    pub unstake_nonce_account: Box<Account<'info, UnstakeNonceAccount>>,
+
     #[account(
         init,
         space = 8 + std::mem::size_of::<UnstakeAccount>(),
         payer = rent_payer,
         rent_exempt = enforce,
+        seeds = [
+            &stake_manager.key().to_bytes(),
+            &burn_lsd_token_from.owner.to_bytes(),
+            unstake_nonce_account.nonce.to_le_bytes().as_ref(),
+        ],
+        bump,
     )]
     pub unstake_account: Box<Account<'info, UnstakeAccount>>,
 
+    /// CHECK: referred account of burn_lsd_token_from, may not exist
+    #[account(
+        mut,
+        seeds = [
+            &stake_manager.key().to_bytes(),
+            &burn_lsd_token_from.key().to_bytes(),
+            ReferredAccount::SEED,
+        ],
+        bump,
+    )]
+    pub referred_account: UncheckedAccount<'info>,
+
+    // required when referred_account exists
+    #[account(
+        mut,
+        seeds = [
+            &stake_manager.key().to_bytes(),
+            &referrer_account.referrer.to_bytes(),
+            ReferrerAccount::SEED,
+        ],
+        bump = referrer_account.bump,
+    )]
+    pub referrer_account: Option<Box<Account<'info, ReferrerAccount>>>,
+
     #[account(
         mut,
         owner = system_program::ID
@@ -45,14 +92,30 @@ pub struct Unstake<'info> {
 pub struct EventUnstake {
     pub era: u64,
     pub staker: Pubkey,
+    pub recipient: Pubkey,
     pub burn_lsd_token_from: Pubkey,
     pub unstake_account: Pubkey,
     pub unstake_amount: u64,
     pub sol_amount: u64,
+    pub unstake_fee: u64,
+    pub queue_position: u64,
+    pub nonce: u64,
+    pub funding_era: u64,
 }
 
 impl<'info> Unstake<'info> {
-    pub fn process(&mut self, unstake_amount: u64) -> Result<()> {
+    pub fn process(
+        &mut self,
+        unstake_amount: u64,
+        recipient: Option<Pubkey>,
+        unstake_nonce_account_bump: u8,
+    ) -> Result<()> {
+        require!(!self.stake_manager.paused, Errors::StakeManagerPaused);
+        require_eq!(
+            self.stake_manager.version,
+            StakeManager::CURRENT_VERSION,
+            Errors::StakeManagerNotMigrated
+        );
         require_gt!(unstake_amount, 0, Errors::UnstakeAmountIsZero);
 
         if self
@@ -75,10 +138,47 @@ impl<'info> Unstake<'info> {
             return err!(Errors::AuthorityNotMatch);
         }
 
-        let sol_amount = self.stake_manager.calc_sol_amount(unstake_amount)?;
+        // only the owner or an approved delegate gets here, either may redirect the sol
+        let recipient = recipient.unwrap_or(self.burn_lsd_token_from.owner);
+
+        let unstake_fee = self
+            .stake_manager
+            .calc_bps_fee(unstake_amount, self.stake_manager.unstake_fee_bps)?;
+        let sol_amount = self
+            .stake_manager
+            .calc_sol_amount(unstake_amount - unstake_fee)?;
+        require_gt!(sol_amount, 0, Errors::UnstakeAmountIsZero);
+
+        // unstake fee is burned with the rest and minted with the next era's fees
+        self.stake_manager.pending_fee += unstake_fee;
         self.stake_manager.era_unbond += sol_amount;
         self.stake_manager.active -= sol_amount;
 
+        let queue_position = self.stake_manager.add_unstake(sol_amount);
+
+        // burned lsd token is taken from the referred amount first
+        if self.referred_account.owner == &crate::ID {
+            let mut data = self.referred_account.try_borrow_mut_data()?;
+            let mut referred_account = ReferredAccount::try_deserialize(&mut &data[..])?;
+            let referrer_account = self
+                .referrer_account
+                .as_mut()
+                .filter(|e| e.referrer == referred_account.referrer)
+                .ok_or_else(|| error!(Errors::ReferrerNotMatch))?;
+
+            self.stake_manager.sync_referral_active(
+                referrer_account,
+                &mut referred_account,
+                self.burn_lsd_token_from.amount,
+            )?;
+            self.stake_manager.sub_referral_active(
+                referrer_account,
+                &mut referred_account,
+                unstake_amount,
+            )?;
+            referred_account.try_serialize(&mut &mut data[..])?;
+        }
+
         // burn lsd token
         burn(
             CpiContext::new(
@@ -94,18 +194,30 @@ impl<'info> Unstake<'info> {
 
         self.unstake_account.set_inner(UnstakeAccount {
             stake_manager: self.stake_manager.key(),
-            recipient: self.burn_lsd_token_from.owner,
+            recipient,
             amount: sol_amount,
             created_epoch: self.clock.epoch,
+            queue_position,
+            rent_payer: self.rent_payer.key(),
+            era: self.stake_manager.unstake_funding_era(),
         });
 
+        let nonce = self.unstake_nonce_account.nonce;
+        self.unstake_nonce_account.bump = unstake_nonce_account_bump;
+        self.unstake_nonce_account.nonce += 1;
+
         emit!(EventUnstake {
             era: self.stake_manager.latest_era,
             staker: self.burn_lsd_token_from.owner,
+            recipient,
             burn_lsd_token_from: self.burn_lsd_token_from.key(),
             unstake_account: self.unstake_account.key(),
             unstake_amount,
-            sol_amount
+            sol_amount,
+            unstake_fee,
+            queue_position,
+            nonce,
+            funding_era: self.unstake_account.era
         });
 
         Ok(())
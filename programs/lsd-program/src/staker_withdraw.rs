@@ -17,10 +17,7 @@ pub struct Withdraw<'info> {
     )]
     pub stake_pool: SystemAccount<'info>,
 
-    #[account(
-        mut,
-        close = recipient,
-    )]
+    #[account(mut)]
     pub unstake_account: Account<'info, UnstakeAccount>,
 
     #[account(
@@ -29,6 +26,12 @@ pub struct Withdraw<'info> {
     )]
     pub recipient: SystemAccount<'info>,
 
+    #[account(
+        mut,
+        address = unstake_account.rent_payer @ Errors::RentPayerNotMatch
+    )]
+    pub rent_payer: SystemAccount<'info>,
+
     pub clock: Sysvar<'info, Clock>,
     pub system_program: Program<'info, System>,
 }
@@ -39,38 +42,53 @@ pub struct EventWithdraw {
     pub staker: Pubkey,
     pub unstake_account: Pubkey,
     pub withdraw_amount: u64,
+    pub remaining_amount: u64,
 }
 
-impl<'info> Withdraw<'info> {
-    pub fn process(&mut self) -> Result<()> {
-        require_keys_eq!(
-            self.unstake_account.stake_manager,
-            self.stake_manager.key(),
-            Errors::InvalidUnstakeAccount
-        );
+fn check_unstake_account(
+    stake_manager: &Account<StakeManager>,
+    unstake_account: &UnstakeAccount,
+    epoch: u64,
+) -> Result<()> {
+    require_keys_eq!(
+        unstake_account.stake_manager,
+        stake_manager.key(),
+        Errors::InvalidUnstakeAccount
+    );
 
-        require_gt!(
-            self.unstake_account.amount,
-            0,
-            Errors::UnstakeAccountAmountZero
-        );
+    require_gt!(unstake_account.amount, 0, Errors::UnstakeAccountAmountZero);
 
-        require_gte!(
-            self.clock.epoch,
-            self.unstake_account.created_epoch + self.stake_manager.unbonding_duration,
-            Errors::UnstakeAccountNotClaimable
-        );
+    require!(
+        stake_manager.is_era_funded(unstake_account.era),
+        Errors::UnstakeAccountNotClaimable
+    );
+    require_gte!(
+        epoch,
+        stake_manager.calc_unstake_min_epoch(unstake_account.created_epoch),
+        Errors::UnstakeAccountNotClaimable
+    );
+
+    Ok(())
+}
+
+impl<'info> Withdraw<'info> {
+    pub fn process(&mut self) -> Result<()> {
+        check_unstake_account(&self.stake_manager, &self.unstake_account, self.clock.epoch)?;
 
         let pool_balance = self.stake_pool.lamports();
-        let withdraw_amount = self.unstake_account.amount;
+        self.stake_manager.update_unstake_fulfilled(pool_balance);
 
         let available_for_withdraw = pool_balance - self.stake_manager.rent_exempt_for_pool_acc;
-        if withdraw_amount > available_for_withdraw {
+        let withdraw_amount = self
+            .stake_manager
+            .claim_unstake(&mut self.unstake_account, available_for_withdraw);
+        if withdraw_amount == 0 {
             return err!(Errors::PoolBalanceNotEnough);
         }
-
-        // disable unstake-account
-        self.unstake_account.amount = 0;
+        self.stake_manager.tracked_pool_balance = self
+            .stake_manager
+            .tracked_pool_balance
+            .saturating_sub(withdraw_amount);
 
         transfer(
             CpiContext::new_with_signer(
@@ -88,12 +106,176 @@ impl<'info> Withdraw<'info> {
             withdraw_amount,
         )?;
 
+        let remaining_amount = self.unstake_account.amount;
+        if remaining_amount == 0 {
+            // refund rent to the payer of unstake
+            self.unstake_account
+                .close(self.rent_payer.to_account_info())?;
+        }
+
         emit!(EventWithdraw {
             era: self.stake_manager.latest_era,
             staker: self.recipient.key(),
             unstake_account: self.unstake_account.key(),
-            withdraw_amount
+            withdraw_amount,
+            remaining_amount
         });
         Ok(())
     }
 }
+
+#[derive(Accounts)]
+pub struct GetUnstakeClaimEpoch<'info> {
+    pub stake_manager: Box<Account<'info, StakeManager>>,
+
+    #[account(
+        has_one = stake_manager @ Errors::InvalidUnstakeAccount
+    )]
+    pub unstake_account: Account<'info, UnstakeAccount>,
+
+    pub clock: Sysvar<'info, Clock>,
+}
+
+impl<'info> GetUnstakeClaimEpoch<'info> {
+    pub fn process(&self) -> Result<u64> {
+        Ok(self
+            .stake_manager
+            .calc_unstake_claim_epoch(self.unstake_account.era, self.clock.epoch)
+            .max(
+                self.stake_manager
+                    .calc_unstake_min_epoch(self.unstake_account.created_epoch),
+            ))
+    }
+}
+
+#[derive(Accounts)]
+pub struct GetUnstakeQueueAhead<'info> {
+    pub stake_manager: Box<Account<'info, StakeManager>>,
+
+    #[account(
+        has_one = stake_manager @ Errors::InvalidUnstakeAccount
+    )]
+    pub unstake_account: Account<'info, UnstakeAccount>,
+}
+
+impl<'info> GetUnstakeQueueAhead<'info> {
+    pub fn process(&self) -> Result<u64> {
+        Ok(self
+            .stake_manager
+            .calc_unstake_queue_ahead(&self.unstake_account))
+    }
+}
+
+#[derive(Accounts)]
+pub struct WithdrawBatch<'info> {
+    #[account(mut)]
+    pub stake_manager: Box<Account<'info, StakeManager>>,
+
+    #[account(
+        mut,
+        seeds = [
+            &stake_manager.key().to_bytes(),
+            StakeManager::POOL_SEED,
+        ],
+        bump = stake_manager.pool_seed_bump
+    )]
+    pub stake_pool: SystemAccount<'info>,
+
+    #[account(mut)]
+    pub recipient: SystemAccount<'info>,
+
+    pub clock: Sysvar<'info, Clock>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> WithdrawBatch<'info> {
+    // remaining accounts: [unstake_account, rent_payer] pairs
+    pub fn process(&mut self, remaining_accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+        let remaining_accounts = remaining_accounts.chunks_exact(2);
+        require!(
+            remaining_accounts.len() > 0 && remaining_accounts.remainder().is_empty(),
+            Errors::RemainingAccountsNotMatch
+        );
+
+        let pool_balance = self.stake_pool.lamports();
+        self.stake_manager.update_unstake_fulfilled(pool_balance);
+
+        let mut available_for_withdraw = pool_balance - self.stake_manager.rent_exempt_for_pool_acc;
+        let mut total_withdraw_amount: u64 = 0;
+        let mut claimed_unstake_accounts = Vec::new();
+
+        for accounts in remaining_accounts {
+            let (unstake_account_info, rent_payer_info) = (&accounts[0], &accounts[1]);
+            require!(
+                unstake_account_info.is_writable && rent_payer_info.is_writable,
+                Errors::RemainingAccountsNotMatch
+            );
+
+            let mut unstake_account = Account::<UnstakeAccount>::try_from(unstake_account_info)?;
+            check_unstake_account(&self.stake_manager, &unstake_account, self.clock.epoch)?;
+
+            require_keys_eq!(
+                unstake_account.recipient,
+                self.recipient.key(),
+                Errors::UnstakeRecipientNotMatch
+            );
+            require_keys_eq!(
+                unstake_account.rent_payer,
+                rent_payer_info.key(),
+                Errors::RentPayerNotMatch
+            );
+
+            let withdraw_amount = self
+                .stake_manager
+                .claim_unstake(&mut unstake_account, available_for_withdraw);
+            available_for_withdraw -= withdraw_amount;
+            total_withdraw_amount += withdraw_amount;
+
+            let remaining_amount = unstake_account.amount;
+            if remaining_amount == 0 {
+                claimed_unstake_accounts.push((unstake_account, rent_payer_info));
+            } else {
+                unstake_account.exit(&crate::ID)?;
+            }
+
+            emit!(EventWithdraw {
+                era: self.stake_manager.latest_era,
+                staker: self.recipient.key(),
+                unstake_account: unstake_account_info.key(),
+                withdraw_amount,
+                remaining_amount
+            });
+        }
+
+        if total_withdraw_amount == 0 {
+            return err!(Errors::PoolBalanceNotEnough);
+        }
+        self.stake_manager.tracked_pool_balance = self
+            .stake_manager
+            .tracked_pool_balance
+            .saturating_sub(total_withdraw_amount);
+
+        transfer(
+            CpiContext::new_with_signer(
+                self.system_program.to_account_info(),
+                Transfer {
+                    from: self.stake_pool.to_account_info(),
+                    to: self.recipient.to_account_info(),
+                },
+                &[&[
+                    &self.stake_manager.key().to_bytes(),
+                    StakeManager::POOL_SEED,
+                    &[self.stake_manager.pool_seed_bump],
+                ]],
+            ),
+            total_withdraw_amount,
+        )?;
+
+        // closed after the transfer like withdraw, refunds rent to the payer of unstake
+        for (unstake_account, rent_payer_info) in claimed_unstake_accounts {
+            unstake_account.close(rent_payer_info.clone())?;
+        }
+
+        Ok(())
+    }
+}
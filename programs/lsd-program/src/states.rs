@@ -5,20 +5,29 @@ use anchor_lang::prelude::*;
 #[derive(Debug)]
 pub struct Stack {
     pub admin: Pubkey,
-    pub stack_fee_commission: u64, // decimals 9
+    pub stack_fee_commission: u64, // decimals 9, copied by stake managers initialized later
     pub stake_managers_len_limit: u64,
     pub entrusted_stake_managers: Vec<Pubkey>,
+    pub max_deposit_fee_bps: u64,
+    pub max_unstake_fee_bps: u64,
+    pub max_platform_fee_commission: u64, // decimals 9
+    pub stack_fee_recipient: Pubkey,      // owner of the token accounts stack fee is claimed to
 }
 
 impl Stack {
     pub const DEFAULT_STACK_FEE_COMMISSION: u64 = 100_000_000;
     pub const DEFAULT_STAKE_MANAGERS_LEN_LIMIT: u64 = 20;
+    pub const DEFAULT_MAX_DEPOSIT_FEE_BPS: u64 = 100;
+    pub const DEFAULT_MAX_UNSTAKE_FEE_BPS: u64 = 100;
+    pub const DEFAULT_MAX_PLATFORM_FEE_COMMISSION: u64 = 300_000_000;
 
-    pub fn calc_stack_fee(&self, platform_fee_raw: u64) -> Result<u64> {
-        u64::try_from(
-            (platform_fee_raw as u128) * (self.stack_fee_commission as u128) / (1e9 as u128),
-        )
-        .map_err(|_| error!(Errors::CalculationFail))
+    pub fn check_fee_commission(fee_commission: u64) -> Result<()> {
+        require_gte!(
+            StakeManager::CAL_BASE,
+            fee_commission,
+            Errors::StackFeeCommissionOverLimit
+        );
+        Ok(())
     }
 }
 
@@ -34,8 +43,8 @@ pub struct StakeManager {
 
     pub min_stake_amount: u64,
     pub platform_fee_commission: u64, // decimals 9
-    pub stack_fee_commission: u64,
-    pub rate_change_limit: u64, // decimals 9
+    pub stack_fee_commission: u64,    // decimals 9, changed through the timelock
+    pub rate_change_limit: u64,       // decimals 9
     pub stake_accounts_len_limit: u64,
     pub split_accounts_len_limit: u64,
     pub unbonding_duration: u64,
@@ -49,8 +58,100 @@ pub struct StakeManager {
     pub validators: Vec<Pubkey>,
     pub stake_accounts: Vec<Pubkey>,
     pub split_accounts: Vec<Pubkey>,
-    pub era_rates: Vec<EraRate>,
+    pub era_rates: Vec<EraRate>, // eras are epochs, entries can be several epochs apart
     pub era_process_data: EraProcessData,
+
+    pub unstake_requested: u64, // cumulative sol amount requested by unstake
+    pub unstake_fulfilled: u64, // cumulative sol amount reserved for the unstake queue
+    pub unstake_claimed: u64,   // cumulative sol amount withdrawn by unstakers
+
+    pub referral_fee_commission: u64, // decimals 9, share of platform fee
+    pub referral_active: u64,         // referred lsd token amount
+    pub referral_fee_per_active: u128, // decimals 18
+
+    pub platform_fee_recipient: Pubkey, // owner of the platform fee token account
+
+    pub deposit_fee_bps: u64,
+    pub unstake_fee_bps: u64,
+    pub pending_fee: u64, // lsd token amount of deposit and unstake fees waiting to be minted
+
+    pub config_timelock_epochs: u64,
+    pub pending_configs: Vec<PendingConfig>,
+
+    pub fee_manager: Pubkey,
+    pub validator_manager: Pubkey,
+    pub pauser: Pubkey,
+    pub param_manager: Pubkey,
+    pub paused: bool,
+
+    pub deposit_authority: Option<Pubkey>, // pool is permissioned when set
+
+    pub max_active: u64,          // 0 means no cap
+    pub epoch_deposit_cap: u64,   // 0 means no cap
+    pub epoch_deposit_epoch: u64, // epoch of epoch_deposited
+    pub epoch_deposited: u64,
+
+    pub insurance_fee_commission: u64, // decimals 9, share of platform fee left after referral fee
+    pub insurance_buffer: u64,         // lsd token amount in the pool escrow reserved for losses
+
+    pub rate_up_limit: u64,   // decimals 9, 0 falls back to rate_change_limit
+    pub rate_down_limit: u64, // decimals 9, 0 falls back to rate_change_limit
+
+    pub tracked_pool_balance: u64, // pool lamports moved by the program, excluding rent
+    pub excess_reward: u64,        // claimed excess lamports not yet counted in the rate
+
+    pub total_unstake_pending: u64, // sol amount of unstake accounts not claimed yet
+    pub total_unstake_claimable: u64, // part of total_unstake_pending reserved in the pool
+
+    pub unbonding_splits: Vec<UnbondingSplit>, // split accounts of era unbond not withdrawn yet
+
+    pub crank_fee_commission: u64, // decimals 9, share of platform fee left after insurance fee
+    pub crank_reward: u64,         // lsd token amount paid per successful era step
+    pub crank_budget: u64,         // lsd token amount in the pool escrow reserved for crankers
+
+    pub rent_as_yield: bool, // rent of new stake accounts stays in the pool as reward instead of refund
+    pub rent_payers: Vec<RentPayer>, // stake and split accounts whose rent goes back to the payer
+
+    pub era_epochs: u64, // epochs covered by the latest era, more than 1 after a catch up
+
+    pub version: u8, // layout version, accounts of an older layout need migrate_stake_manager
+}
+
+// layout of accounts created before the fields above were appended, read once by migrate_stake_manager
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct StakeManagerV0 {
+    pub admin: Pubkey,
+    pub balancer: Pubkey,
+    pub stack: Pubkey,
+    pub lsd_token_mint: Pubkey,
+    pub pool_seed_bump: u8,
+    pub rent_exempt_for_pool_acc: u64,
+
+    pub min_stake_amount: u64,
+    pub platform_fee_commission: u64,
+    pub stack_fee_commission: u64,
+    pub rate_change_limit: u64,
+    pub stake_accounts_len_limit: u64,
+    pub split_accounts_len_limit: u64,
+    pub unbonding_duration: u64,
+
+    pub latest_era: u64,
+    pub rate: u64,
+    pub era_bond: u64,
+    pub era_unbond: u64,
+    pub active: u64,
+    pub total_platform_fee: u64,
+    pub validators: Vec<Pubkey>,
+    pub stake_accounts: Vec<Pubkey>,
+    pub split_accounts: Vec<Pubkey>,
+    pub era_rates: Vec<EraRateV0>,
+    pub era_process_data: EraProcessData,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct EraRateV0 {
+    pub era: u64,
+    pub rate: u64,
 }
 
 #[derive(Clone, Debug, Default, AnchorSerialize, AnchorDeserialize)]
@@ -62,10 +163,59 @@ pub struct EraProcessData {
     pub pending_stake_accounts: Vec<Pubkey>,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, AnchorSerialize, AnchorDeserialize)]
+pub enum ConfigParam {
+    PlatformFeeCommission,
+    DepositFeeBps,
+    UnstakeFeeBps,
+    ReferralFeeCommission,
+    RateChangeLimit,
+    UnbondingDuration,
+    InsuranceFeeCommission,
+    RateUpLimit,
+    RateDownLimit,
+    CrankFeeCommission,
+    MinStakeAmount,
+    PlatformStackFeeCommission,
+    ConfigTimelockEpochs,
+    CrankReward,
+}
+
+// roles the admin can hand to operation keys, balancer stays the rebalancer
+#[derive(Clone, Copy, Debug, PartialEq, Eq, AnchorSerialize, AnchorDeserialize)]
+pub enum Role {
+    FeeManager,
+    ValidatorManager,
+    Pauser,
+    ParamManager,
+}
+
+#[derive(Clone, Debug, AnchorSerialize, AnchorDeserialize)]
+pub struct PendingConfig {
+    pub param: ConfigParam,
+    pub value: u64,
+    pub apply_epoch: u64,
+}
+
+#[derive(Clone, Debug, AnchorSerialize, AnchorDeserialize)]
+pub struct UnbondingSplit {
+    pub split_account: Pubkey,
+    pub era: u64,
+    pub deactivation_epoch: u64,
+}
+
+#[derive(Clone, Debug, AnchorSerialize, AnchorDeserialize)]
+pub struct RentPayer {
+    pub stake_account: Pubkey,
+    pub payer: Pubkey,
+    pub rent: u64,
+}
+
 #[derive(Clone, Debug, Default, AnchorSerialize, AnchorDeserialize)]
 pub struct EraRate {
     pub era: u64,
     pub rate: u64,
+    pub era_epochs: u64, // epochs the rate change covers
 }
 
 impl EraProcessData {
@@ -115,6 +265,200 @@ impl StakeManager {
     pub const DEFAULT_STAKE_ACCOUNT_LEN_LIMIT: u64 = 100;
     pub const DEFAULT_SPLIT_ACCOUNT_LEN_LIMIT: u64 = 20;
     pub const ERA_RATES_LEN_LIMIT: u64 = 10;
+    // a catch up era covers at most 10 epochs, which also caps the scaled rate change limit. a
+    // longer outage takes one era_new_catch_up per 10 epochs, each processed as a full era
+    pub const MAX_CATCH_UP_EPOCHS: u64 = 10;
+    // layout version, a change that appends fields bumps it and extends migrate_stake_manager to
+    // convert the previous layout in the same change, so every version is deployable over the last
+    pub const CURRENT_VERSION: u8 = 1;
+    // stake accounts per batch, meant to stay within the default compute unit limit. not measured
+    // yet, tests/era_batch.rs checks the units under cargo test-sbf --features test-sbf and only
+    // the transaction size otherwise
+    pub const UPDATE_ACTIVE_BATCH_LIMIT: u64 = 20;
+    // era_finalize also carries the era_update_rate accounts and fee mints
+    pub const FINALIZE_BATCH_LIMIT: u64 = 12;
+    pub const REFERRAL_CAL_BASE: u128 = 1_000_000_000_000_000_000;
+    pub const BPS_BASE: u64 = 10_000;
+
+    pub const MIN_MIN_STAKE_AMOUNT: u64 = 1;
+    pub const MAX_MIN_STAKE_AMOUNT: u64 = 1_000_000_000_000;
+    pub const MIN_UNBONDING_DURATION: u64 = 1;
+    pub const MAX_UNBONDING_DURATION: u64 = 30;
+    pub const DEFAULT_CONFIG_TIMELOCK_EPOCHS: u64 = 2;
+    pub const MAX_CONFIG_TIMELOCK_EPOCHS: u64 = 30;
+    pub const MIN_RATE_CHANGE_LIMIT: u64 = 1;
+    pub const MAX_RATE_CHANGE_LIMIT: u64 = 100_000_000;
+    pub const MAX_CRANK_REWARD: u64 = 10_000_000; // lsd token amount per era step
+
+    pub fn role(&self, role: Role) -> Pubkey {
+        match role {
+            Role::FeeManager => self.fee_manager,
+            Role::ValidatorManager => self.validator_manager,
+            Role::Pauser => self.pauser,
+            Role::ParamManager => self.param_manager,
+        }
+    }
+
+    pub fn set_role(&mut self, role: Role, key: Pubkey) {
+        match role {
+            Role::FeeManager => self.fee_manager = key,
+            Role::ValidatorManager => self.validator_manager = key,
+            Role::Pauser => self.pauser = key,
+            Role::ParamManager => self.param_manager = key,
+        }
+    }
+
+    // admin keeps every right of the roles it hands out
+    pub fn has_role(&self, role: Role, key: &Pubkey) -> bool {
+        *key == self.admin || *key == self.role(role)
+    }
+
+    // record a deposit against the pool capacity and the per-epoch cap
+    pub fn add_deposit(&mut self, stake_amount: u64, epoch: u64) -> Result<()> {
+        if self.max_active > 0 {
+            require_gte!(
+                self.max_active,
+                self.active + stake_amount,
+                Errors::PoolCapacityExceeded
+            );
+        }
+
+        if self.epoch_deposit_epoch != epoch {
+            self.epoch_deposit_epoch = epoch;
+            self.epoch_deposited = 0;
+        }
+        self.epoch_deposited += stake_amount;
+        if self.epoch_deposit_cap > 0 {
+            require_gte!(
+                self.epoch_deposit_cap,
+                self.epoch_deposited,
+                Errors::EpochDepositCapExceeded
+            );
+        }
+
+        Ok(())
+    }
+
+    pub fn config_value(&self, param: ConfigParam) -> u64 {
+        match param {
+            ConfigParam::PlatformFeeCommission => self.platform_fee_commission,
+            ConfigParam::DepositFeeBps => self.deposit_fee_bps,
+            ConfigParam::UnstakeFeeBps => self.unstake_fee_bps,
+            ConfigParam::ReferralFeeCommission => self.referral_fee_commission,
+            ConfigParam::RateChangeLimit => self.rate_change_limit,
+            ConfigParam::UnbondingDuration => self.unbonding_duration,
+            ConfigParam::InsuranceFeeCommission => self.insurance_fee_commission,
+            ConfigParam::RateUpLimit => self.rate_up_limit,
+            ConfigParam::RateDownLimit => self.rate_down_limit,
+            ConfigParam::CrankFeeCommission => self.crank_fee_commission,
+            ConfigParam::MinStakeAmount => self.min_stake_amount,
+            ConfigParam::PlatformStackFeeCommission => self.stack_fee_commission,
+            ConfigParam::ConfigTimelockEpochs => self.config_timelock_epochs,
+            ConfigParam::CrankReward => self.crank_reward,
+        }
+    }
+
+    pub fn set_config_value(&mut self, param: ConfigParam, value: u64) {
+        match param {
+            ConfigParam::PlatformFeeCommission => self.platform_fee_commission = value,
+            ConfigParam::DepositFeeBps => self.deposit_fee_bps = value,
+            ConfigParam::UnstakeFeeBps => self.unstake_fee_bps = value,
+            ConfigParam::ReferralFeeCommission => self.referral_fee_commission = value,
+            ConfigParam::RateChangeLimit => self.rate_change_limit = value,
+            ConfigParam::UnbondingDuration => self.unbonding_duration = value,
+            ConfigParam::InsuranceFeeCommission => self.insurance_fee_commission = value,
+            ConfigParam::RateUpLimit => self.rate_up_limit = value,
+            ConfigParam::RateDownLimit => self.rate_down_limit = value,
+            ConfigParam::CrankFeeCommission => self.crank_fee_commission = value,
+            ConfigParam::MinStakeAmount => self.min_stake_amount = value,
+            ConfigParam::PlatformStackFeeCommission => self.stack_fee_commission = value,
+            ConfigParam::ConfigTimelockEpochs => self.config_timelock_epochs = value,
+            ConfigParam::CrankReward => self.crank_reward = value,
+        }
+    }
+
+    pub fn check_min_stake_amount(amount: u64) -> Result<()> {
+        require!(
+            (StakeManager::MIN_MIN_STAKE_AMOUNT..=StakeManager::MAX_MIN_STAKE_AMOUNT)
+                .contains(&amount),
+            Errors::MinStakeAmountOutOfRange
+        );
+        Ok(())
+    }
+
+    pub fn check_unbonding_duration(duration: u64) -> Result<()> {
+        require!(
+            (StakeManager::MIN_UNBONDING_DURATION..=StakeManager::MAX_UNBONDING_DURATION)
+                .contains(&duration),
+            Errors::UnbondingDurationOutOfRange
+        );
+        Ok(())
+    }
+
+    pub fn check_rate_change_limit(rate_change_limit: u64) -> Result<()> {
+        require!(
+            (StakeManager::MIN_RATE_CHANGE_LIMIT..=StakeManager::MAX_RATE_CHANGE_LIMIT)
+                .contains(&rate_change_limit),
+            Errors::RateChangeLimitOutOfRange
+        );
+        Ok(())
+    }
+
+    pub fn check_crank_reward(crank_reward: u64) -> Result<()> {
+        require_gte!(
+            StakeManager::MAX_CRANK_REWARD,
+            crank_reward,
+            Errors::CrankRewardOverLimit
+        );
+        Ok(())
+    }
+
+    pub fn check_platform_fee_commission(
+        stack: &Stack,
+        platform_fee_commission: u64,
+    ) -> Result<()> {
+        require_gte!(
+            stack.max_platform_fee_commission,
+            platform_fee_commission,
+            Errors::PlatformFeeCommissionOverLimit
+        );
+        Ok(())
+    }
+
+    // fee configs capped by the stack, checked again when a queued value is applied
+    // since the stack admin may have lowered the cap meanwhile
+    pub fn check_stack_cap(stack: &Stack, param: ConfigParam, value: u64) -> Result<()> {
+        match param {
+            ConfigParam::PlatformFeeCommission => {
+                StakeManager::check_platform_fee_commission(stack, value)
+            }
+            ConfigParam::DepositFeeBps => {
+                require_gte!(
+                    stack.max_deposit_fee_bps,
+                    value,
+                    Errors::DepositFeeOverLimit
+                );
+                Ok(())
+            }
+            ConfigParam::UnstakeFeeBps => {
+                require_gte!(
+                    stack.max_unstake_fee_bps,
+                    value,
+                    Errors::UnstakeFeeOverLimit
+                );
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn calc_stack_fee(&self, platform_fee_raw: u64) -> Result<u64> {
+        u64::try_from(
+            (platform_fee_raw as u128) * (self.stack_fee_commission as u128)
+                / (StakeManager::CAL_BASE as u128),
+        )
+        .map_err(|_| error!(Errors::CalculationFail))
+    }
 
     pub fn calc_lsd_token_amount(&self, sol_amount: u64) -> Result<u64> {
         u64::try_from((sol_amount as u128) * (StakeManager::CAL_BASE as u128) / (self.rate as u128))
@@ -128,13 +472,153 @@ impl StakeManager {
         .map_err(|_| error!(Errors::CalculationFail))
     }
 
-    pub fn calc_platform_fee(&self, reward_sol: u64) -> Result<u64> {
+    // lsd token amount to mint as fee, worth platform_fee_commission of the reward at the
+    // post-reward rate: fee * new_active / (supply + fee) == reward * commission
+    pub fn calc_platform_fee(
+        &self,
+        reward_sol: u64,
+        new_active: u64,
+        lsd_token_supply: u64,
+    ) -> Result<u64> {
+        if reward_sol == 0 || lsd_token_supply == 0 {
+            return Ok(0);
+        }
+
+        let fee_sol = (reward_sol as u128) * (self.platform_fee_commission as u128)
+            / (StakeManager::CAL_BASE as u128);
+        let remain_sol = (new_active as u128)
+            .checked_sub(fee_sol)
+            .filter(|remain_sol| *remain_sol > 0)
+            .ok_or_else(|| error!(Errors::CalculationFail))?;
+
+        u64::try_from(fee_sol * (lsd_token_supply as u128) / remain_sol)
+            .map_err(|_| error!(Errors::CalculationFail))
+    }
+
+    pub fn calc_bps_fee(&self, lsd_token_amount: u64, fee_bps: u64) -> Result<u64> {
+        u64::try_from(
+            (lsd_token_amount as u128) * (fee_bps as u128) / (StakeManager::BPS_BASE as u128),
+        )
+        .map_err(|_| error!(Errors::CalculationFail))
+    }
+
+    pub fn calc_referral_fee(&self, platform_fee: u64) -> Result<u64> {
+        if self.referral_active == 0 {
+            return Ok(0);
+        }
+
+        u64::try_from(
+            (platform_fee as u128) * (self.referral_fee_commission as u128)
+                / (StakeManager::CAL_BASE as u128),
+        )
+        .map_err(|_| error!(Errors::CalculationFail))
+    }
+
+    pub fn calc_insurance_fee(&self, platform_fee: u64) -> Result<u64> {
         u64::try_from(
-            (reward_sol as u128) * (self.platform_fee_commission as u128) / (self.rate as u128),
+            (platform_fee as u128) * (self.insurance_fee_commission as u128)
+                / (StakeManager::CAL_BASE as u128),
         )
         .map_err(|_| error!(Errors::CalculationFail))
     }
 
+    pub fn calc_crank_fee(&self, platform_fee: u64) -> Result<u64> {
+        u64::try_from(
+            (platform_fee as u128) * (self.crank_fee_commission as u128)
+                / (StakeManager::CAL_BASE as u128),
+        )
+        .map_err(|_| error!(Errors::CalculationFail))
+    }
+
+    // credit the cranker out of the crank budget, returns the paid lsd token amount
+    pub fn pay_crank_reward(&mut self, cranker_account: &mut CrankerAccount) -> u64 {
+        let reward = self.crank_reward.min(self.crank_budget);
+
+        self.crank_budget -= reward;
+        cranker_account.unclaimed_reward += reward;
+
+        reward
+    }
+
+    // lsd token amount to burn from the insurance buffer to keep the current rate after a loss
+    pub fn calc_insurance_burn(&self, new_active: u64, lsd_token_supply: u64) -> Result<u64> {
+        let target_supply = self.calc_lsd_token_amount(new_active)?;
+
+        Ok(lsd_token_supply
+            .saturating_sub(target_supply)
+            .min(self.insurance_buffer))
+    }
+
+    pub fn distribute_referral_fee(&mut self, referral_fee: u64) {
+        if self.referral_active == 0 {
+            return;
+        }
+
+        self.referral_fee_per_active += (referral_fee as u128) * StakeManager::REFERRAL_CAL_BASE
+            / (self.referral_active as u128);
+    }
+
+    // move the fee accrued by the referrer's active stake into its unclaimed fee
+    pub fn settle_referral_fee(&self, referrer_account: &mut ReferrerAccount) -> Result<()> {
+        let accrued = (referrer_account.active as u128) * self.referral_fee_per_active
+            / StakeManager::REFERRAL_CAL_BASE;
+        let pending = u64::try_from(accrued - referrer_account.fee_debt)
+            .map_err(|_| error!(Errors::CalculationFail))?;
+
+        referrer_account.unclaimed_fee += pending;
+        referrer_account.fee_debt = accrued;
+        Ok(())
+    }
+
+    pub fn add_referral_active(
+        &mut self,
+        referrer_account: &mut ReferrerAccount,
+        referred_account: &mut ReferredAccount,
+        stake_amount: u64,
+        lsd_token_amount: u64,
+    ) -> Result<()> {
+        self.settle_referral_fee(referrer_account)?;
+
+        referrer_account.total_deposit += stake_amount;
+        referrer_account.active += lsd_token_amount;
+        referrer_account.fee_debt = (referrer_account.active as u128)
+            * self.referral_fee_per_active
+            / StakeManager::REFERRAL_CAL_BASE;
+        referred_account.amount += lsd_token_amount;
+        self.referral_active += lsd_token_amount;
+        Ok(())
+    }
+
+    // referred lsd token that was unstaked or moved out of the token account stops sharing the fee
+    pub fn sub_referral_active(
+        &mut self,
+        referrer_account: &mut ReferrerAccount,
+        referred_account: &mut ReferredAccount,
+        lsd_token_amount: u64,
+    ) -> Result<()> {
+        let lsd_token_amount = lsd_token_amount.min(referred_account.amount);
+        self.settle_referral_fee(referrer_account)?;
+
+        referrer_account.active = referrer_account.active.saturating_sub(lsd_token_amount);
+        referrer_account.fee_debt = (referrer_account.active as u128)
+            * self.referral_fee_per_active
+            / StakeManager::REFERRAL_CAL_BASE;
+        referred_account.amount -= lsd_token_amount;
+        self.referral_active = self.referral_active.saturating_sub(lsd_token_amount);
+        Ok(())
+    }
+
+    // cap the referred amount by what the token account still holds
+    pub fn sync_referral_active(
+        &mut self,
+        referrer_account: &mut ReferrerAccount,
+        referred_account: &mut ReferredAccount,
+        token_account_amount: u64,
+    ) -> Result<()> {
+        let moved_out = referred_account.amount.saturating_sub(token_account_amount);
+        self.sub_referral_active(referrer_account, referred_account, moved_out)
+    }
+
     pub fn calc_rate(&self, sol_amount: u64, lsd_token_amount: u64) -> Result<u64> {
         if sol_amount == 0 || lsd_token_amount == 0 {
             return Ok(StakeManager::CAL_BASE);
@@ -159,20 +643,768 @@ impl StakeManager {
         u64::try_from((diff as u128) * (StakeManager::CAL_BASE as u128) / (old_rate as u128))
             .map_err(|_| error!(Errors::CalculationFail))
     }
+
+    // limit of a change from the current rate to new_rate, 0 means no limit
+    pub fn rate_change_limit_of(&self, new_rate: u64) -> u64 {
+        let direction_limit = if new_rate >= self.rate {
+            self.rate_up_limit
+        } else {
+            self.rate_down_limit
+        };
+
+        if direction_limit > 0 {
+            direction_limit
+        } else {
+            self.rate_change_limit
+        }
+    }
+
+    // lamports that landed in the pool outside the program, such as tips
+    pub fn calc_pool_excess(&self, pool_balance: u64) -> u64 {
+        pool_balance
+            .saturating_sub(self.rent_exempt_for_pool_acc)
+            .saturating_sub(self.tracked_pool_balance)
+    }
+
+    // claimed excess lamports are bonded next era and counted as reward by the rate update
+    pub fn add_excess_lamports(&mut self, amount: u64) {
+        self.tracked_pool_balance += amount;
+        self.era_bond += amount;
+        self.active += amount;
+        self.excess_reward += amount;
+    }
+
+    // reserve free pool balance for the unstake queue in FIFO order
+    pub fn update_unstake_fulfilled(&mut self, pool_balance: u64) {
+        let reserved = self.unstake_fulfilled - self.unstake_claimed;
+        let available = pool_balance
+            .saturating_sub(self.rent_exempt_for_pool_acc)
+            .saturating_sub(reserved);
+
+        let unstake_fulfilled = self
+            .unstake_requested
+            .min(self.unstake_fulfilled.saturating_add(available));
+        self.total_unstake_claimable += unstake_fulfilled - self.unstake_fulfilled;
+        self.unstake_fulfilled = unstake_fulfilled;
+    }
+
+    pub fn add_unstake(&mut self, sol_amount: u64) -> u64 {
+        let queue_position = self.unstake_requested;
+        self.unstake_requested += sol_amount;
+        self.total_unstake_pending += sol_amount;
+
+        queue_position
+    }
+
+    // unstakes of the current era are unbonded by the next era
+    pub fn unstake_funding_era(&self) -> u64 {
+        self.latest_era + 1
+    }
+
+    // era a catch up jumps to, longer outages take several catch up cycles
+    pub fn calc_catch_up_era(&self, epoch: u64) -> u64 {
+        epoch.min(self.latest_era + StakeManager::MAX_CATCH_UP_EPOCHS)
+    }
+
+    // first era whose unstakes are unbonded by the latest era
+    pub fn latest_era_start(&self) -> u64 {
+        self.latest_era + 1 - self.era_epochs.max(1)
+    }
+
+    fn is_era_unbonded(&self, era: u64) -> bool {
+        era < self.latest_era_start()
+            || era <= self.latest_era && !self.era_process_data.need_unbond()
+    }
+
+    // remember who paid the rent of a new stake account unless rent is counted as yield,
+    // one entry per tracked stake or split account at most. the entry is only recorded while
+    // it fits in the stake manager account of account_len bytes, otherwise the rent is counted
+    // as yield on withdraw like with rent_as_yield until realloc_stake_manager makes room
+    pub fn add_rent_payer(
+        &mut self,
+        stake_account: Pubkey,
+        payer: Pubkey,
+        rent: u64,
+        account_len: usize,
+    ) -> Result<()> {
+        if !self.rent_as_yield {
+            require_gt!(
+                self.stake_accounts_len_limit + self.split_accounts_len_limit,
+                self.rent_payers.len() as u64,
+                Errors::RentPayersLenOverLimit
+            );
+            let used_len = 8 + self
+                .try_to_vec()
+                .map_err(|_| error!(Errors::CalculationFail))?
+                .len();
+            if used_len + std::mem::size_of::<RentPayer>() <= account_len {
+                self.rent_payers.push(RentPayer {
+                    stake_account,
+                    payer,
+                    rent,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    // rent that will be refunded to payers instead of backing active or unstakes
+    pub fn refundable_rent(&self) -> u64 {
+        self.rent_payers.iter().map(|e| e.rent).sum()
+    }
+
+    pub fn take_rent_payer(&mut self, stake_account: &Pubkey) -> Option<RentPayer> {
+        let index = self
+            .rent_payers
+            .iter()
+            .position(|e| e.stake_account == *stake_account)?;
+        Some(self.rent_payers.remove(index))
+    }
+
+    // the era's unbond is done and every split account it made is withdrawn into the pool
+    pub fn is_era_funded(&self, era: u64) -> bool {
+        self.is_era_unbonded(era) && !self.unbonding_splits.iter().any(|e| e.era <= era)
+    }
+
+    // unbonding_duration stays a minimum delay between unstake and withdraw on top of
+    // the funding era
+    pub fn calc_unstake_min_epoch(&self, created_epoch: u64) -> u64 {
+        created_epoch + self.unbonding_duration
+    }
+
+    // epoch from which the unstakes of the era can be claimed, split accounts are
+    // withdrawable the epoch after deactivation
+    pub fn calc_unstake_claim_epoch(&self, era: u64, epoch: u64) -> u64 {
+        let split_epoch = self
+            .unbonding_splits
+            .iter()
+            .filter(|e| e.era <= era)
+            .map(|e| e.deactivation_epoch + 1)
+            .max()
+            .unwrap_or(0);
+        let unbond_epoch = if self.is_era_unbonded(era) {
+            0
+        } else {
+            era.max(epoch) + 1
+        };
+
+        epoch.max(split_epoch).max(unbond_epoch)
+    }
+
+    // pool balance that is neither rent nor reserved for unstakers
+    pub fn calc_bondable(&self, pool_balance: u64) -> u64 {
+        pool_balance
+            .saturating_sub(self.rent_exempt_for_pool_acc)
+            .saturating_sub(self.total_unstake_claimable)
+    }
+
+    pub fn calc_unstake_claimable(&self, unstake_account: &UnstakeAccount) -> u64 {
+        self.unstake_fulfilled
+            .saturating_sub(unstake_account.queue_position)
+            .min(unstake_account.amount)
+    }
+
+    // claim from the head of the unstake account, returns the claimed sol amount
+    pub fn claim_unstake(&mut self, unstake_account: &mut UnstakeAccount, available: u64) -> u64 {
+        let claim_amount = self.calc_unstake_claimable(unstake_account).min(available);
+
+        unstake_account.amount -= claim_amount;
+        unstake_account.queue_position += claim_amount;
+        self.unstake_claimed += claim_amount;
+        self.total_unstake_pending = self.total_unstake_pending.saturating_sub(claim_amount);
+        self.total_unstake_claimable = self.total_unstake_claimable.saturating_sub(claim_amount);
+
+        claim_amount
+    }
+
+    // sol amount queued ahead of the unstake account that is not fulfilled yet
+    pub fn calc_unstake_queue_ahead(&self, unstake_account: &UnstakeAccount) -> u64 {
+        unstake_account
+            .queue_position
+            .saturating_sub(self.unstake_fulfilled)
+    }
 }
 
 #[account]
 #[derive(Debug)]
 pub struct UnstakeAccount {
+    pub stake_manager: Pubkey,
+    pub recipient: Pubkey,
+    pub amount: u64, // unclaimed sol amount
+    pub created_epoch: u64,
+    pub queue_position: u64, // position of the unclaimed amount in the unstake queue
+    pub rent_payer: Pubkey,
+    pub era: u64, // era whose unbond funds the unstake
+}
+
+// layout of unstake accounts created before the unstake queue, converted by migrate_unstake_account
+#[derive(AnchorSerialize, AnchorDeserialize)]
+pub struct UnstakeAccountV0 {
     pub stake_manager: Pubkey,
     pub recipient: Pubkey,
     pub amount: u64,
     pub created_epoch: u64,
 }
 
+impl UnstakeAccountV0 {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8;
+}
+
 #[account]
 #[derive(Debug)]
 pub struct StackFeeAccount {
     pub bump: u8,
-    pub amount: u64,
+    pub amount: u64, // cumulative stack fee minted into the escrow
+}
+
+#[account]
+#[derive(Debug)]
+pub struct UnstakeNonceAccount {
+    pub bump: u8,
+    pub nonce: u64, // nonce of the next unstake account
+}
+
+impl UnstakeNonceAccount {
+    pub const SEED: &'static [u8] = b"unstake_nonce";
+}
+
+#[account]
+#[derive(Debug)]
+pub struct ReferrerAccount {
+    pub stake_manager: Pubkey,
+    pub referrer: Pubkey,
+    pub bump: u8,
+    pub total_deposit: u64, // cumulative sol amount staked with this referrer
+    pub active: u64,        // referred lsd token amount that shares the referral fee
+    pub fee_debt: u128,
+    pub unclaimed_fee: u64, // lsd token amount
+}
+
+impl ReferrerAccount {
+    pub const SEED: &'static [u8] = b"referrer";
+}
+
+// lsd token minted with a referrer into a token account, per token account
+#[account]
+#[derive(Debug)]
+pub struct ReferredAccount {
+    pub stake_manager: Pubkey,
+    pub token_account: Pubkey,
+    pub referrer: Pubkey,
+    pub bump: u8,
+    pub amount: u64, // lsd token amount still counted for the referrer
+}
+
+impl ReferredAccount {
+    pub const SEED: &'static [u8] = b"referred";
+}
+
+#[account]
+#[derive(Debug)]
+pub struct DepositAllowlistAccount {
+    pub stake_manager: Pubkey,
+    pub depositor: Pubkey,
+    pub bump: u8,
+}
+
+impl DepositAllowlistAccount {
+    pub const SEED: &'static [u8] = b"deposit_allowlist";
+}
+
+#[account]
+#[derive(Debug)]
+pub struct CrankerAccount {
+    pub stake_manager: Pubkey,
+    pub cranker: Pubkey,
+    pub bump: u8,
+    pub unclaimed_reward: u64, // lsd token amount
+}
+
+impl CrankerAccount {
+    pub const SEED: &'static [u8] = b"cranker";
+}
+
+#[account]
+#[derive(Debug)]
+pub struct Governance {
+    pub signers: Vec<Pubkey>,
+    pub threshold: u8,
+    pub signer_bump: u8,
+    pub signers_seqno: u32, // bumped on every signer set change to void pending proposals
+}
+
+impl Governance {
+    pub const SIGNER_SEED: &'static [u8] = b"governance_signer";
+    pub const MAX_SIGNERS_LEN: usize = 10;
+    // sized for the max signer set, so signers can be replaced without realloc
+    pub const SPACE: usize = 8 + 4 + Self::MAX_SIGNERS_LEN * 32 + 1 + 1 + 4;
+
+    pub fn check_signers(signers: &[Pubkey], threshold: u8) -> Result<()> {
+        require!(
+            !signers.is_empty() && signers.len() <= Self::MAX_SIGNERS_LEN,
+            Errors::GovernanceSignersInvalid
+        );
+        for (i, signer) in signers.iter().enumerate() {
+            require!(
+                !signers[..i].contains(signer),
+                Errors::GovernanceSignersInvalid
+            );
+        }
+        require!(
+            threshold > 0 && threshold as usize <= signers.len(),
+            Errors::GovernanceThresholdInvalid
+        );
+        Ok(())
+    }
+
+    pub fn signer_index(&self, signer: &Pubkey) -> Result<usize> {
+        self.signers
+            .iter()
+            .position(|e| e == signer)
+            .ok_or_else(|| error!(Errors::GovernanceSignerNotMatch))
+    }
+}
+
+#[derive(Clone, Debug, AnchorSerialize, AnchorDeserialize)]
+pub struct ProposalAccount {
+    pub pubkey: Pubkey,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+#[account]
+#[derive(Debug)]
+pub struct Proposal {
+    pub governance: Pubkey,
+    pub proposer: Pubkey,
+    pub accounts: Vec<ProposalAccount>,
+    pub data: Vec<u8>,
+    pub approvals: Vec<bool>,
+    pub signers_seqno: u32,
+    pub executed: bool,
+    pub cancelled: bool,
+    pub rent_payer: Pubkey,
+}
+
+impl Proposal {
+    // max proposed instruction, sized so create_proposal still fits in one transaction
+    pub const MAX_ACCOUNTS_LEN: usize = 8;
+    pub const MAX_DATA_LEN: usize = 384;
+
+    pub fn space(accounts_len: usize, data_len: usize, signers_len: usize) -> usize {
+        8 + 32
+            + 32
+            + 4
+            + accounts_len * (32 + 1 + 1)
+            + 4
+            + data_len
+            + 4
+            + signers_len
+            + 4
+            + 1
+            + 1
+            + 32
+    }
+
+    pub fn closable(&self, governance: &Governance) -> bool {
+        self.executed || self.cancelled || self.signers_seqno != governance.signers_seqno
+    }
+
+    pub fn approved(&self, governance: &Governance) -> bool {
+        self.signers_seqno == governance.signers_seqno
+            && self.approvals.iter().filter(|e| **e).count() >= governance.threshold as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn stake_manager(rate: u64, platform_fee_commission: u64) -> StakeManager {
+        StakeManager {
+            admin: Pubkey::default(),
+            balancer: Pubkey::default(),
+            stack: Pubkey::default(),
+            lsd_token_mint: Pubkey::default(),
+            pool_seed_bump: 0,
+            rent_exempt_for_pool_acc: 0,
+            min_stake_amount: StakeManager::DEFAULT_MIN_STAKE_AMOUNT,
+            platform_fee_commission,
+            stack_fee_commission: Stack::DEFAULT_STACK_FEE_COMMISSION,
+            rate_change_limit: StakeManager::DEFAULT_RATE_CHANGE_LIMIT,
+            stake_accounts_len_limit: StakeManager::DEFAULT_STAKE_ACCOUNT_LEN_LIMIT,
+            split_accounts_len_limit: StakeManager::DEFAULT_SPLIT_ACCOUNT_LEN_LIMIT,
+            unbonding_duration: StakeManager::DEFAULT_UNBONDING_DURATION,
+            latest_era: 0,
+            rate,
+            era_bond: 0,
+            era_unbond: 0,
+            active: 0,
+            total_platform_fee: 0,
+            validators: vec![],
+            stake_accounts: vec![],
+            split_accounts: vec![],
+            era_rates: vec![],
+            era_process_data: EraProcessData::default(),
+            unstake_requested: 0,
+            unstake_fulfilled: 0,
+            unstake_claimed: 0,
+            referral_fee_commission: 0,
+            referral_active: 0,
+            referral_fee_per_active: 0,
+            platform_fee_recipient: Pubkey::default(),
+            deposit_fee_bps: 0,
+            unstake_fee_bps: 0,
+            pending_fee: 0,
+            config_timelock_epochs: 0,
+            pending_configs: vec![],
+            fee_manager: Pubkey::default(),
+            validator_manager: Pubkey::default(),
+            pauser: Pubkey::default(),
+            param_manager: Pubkey::default(),
+            paused: false,
+            deposit_authority: None,
+            max_active: 0,
+            epoch_deposit_cap: 0,
+            epoch_deposit_epoch: 0,
+            epoch_deposited: 0,
+            insurance_fee_commission: 0,
+            insurance_buffer: 0,
+            rate_up_limit: 0,
+            rate_down_limit: 0,
+            tracked_pool_balance: 0,
+            excess_reward: 0,
+            total_unstake_pending: 0,
+            total_unstake_claimable: 0,
+            unbonding_splits: vec![],
+            crank_fee_commission: 0,
+            crank_reward: 0,
+            crank_budget: 0,
+            rent_as_yield: false,
+            rent_payers: vec![],
+            era_epochs: 0,
+            version: StakeManager::CURRENT_VERSION,
+        }
+    }
+
+    prop_compose! {
+        // (stake manager, old active, lsd token supply, reward)
+        fn era_inputs()(
+            old_active in 1_000_000_000u64..100_000_000_000_000_000,
+            old_rate in StakeManager::CAL_BASE..2 * StakeManager::CAL_BASE,
+            reward_bps in 0u64..1_000,
+            platform_fee_commission in 0u64..=StakeManager::CAL_BASE,
+        ) -> (StakeManager, u64, u64, u64) {
+            let supply =
+                (old_active as u128 * StakeManager::CAL_BASE as u128 / old_rate as u128) as u64;
+            let mut stake_manager = stake_manager(old_rate, platform_fee_commission);
+            stake_manager.rate = stake_manager.calc_rate(old_active, supply).unwrap();
+            (stake_manager, old_active, supply, old_active / 10_000 * reward_bps)
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn rate_is_monotonic_after_fee((stake_manager, old_active, supply, reward) in era_inputs()) {
+            let new_active = old_active + reward;
+            let fee = stake_manager.calc_platform_fee(reward, new_active, supply).unwrap();
+            let new_rate = stake_manager.calc_rate(new_active, supply + fee).unwrap();
+
+            prop_assert!(new_rate >= stake_manager.rate);
+        }
+
+        #[test]
+        fn fee_is_worth_commission_of_reward((stake_manager, old_active, supply, reward) in era_inputs()) {
+            let new_active = old_active + reward;
+            let fee = stake_manager.calc_platform_fee(reward, new_active, supply).unwrap();
+            let new_rate = stake_manager.calc_rate(new_active, supply + fee).unwrap();
+
+            let fee_value = fee as u128 * new_rate as u128 / StakeManager::CAL_BASE as u128;
+            let expected = reward as u128 * stake_manager.platform_fee_commission as u128
+                / StakeManager::CAL_BASE as u128;
+            // rounding of the fee, the rate and the value each lose less than one unit
+            let tolerance = (new_rate as u128 + fee as u128) / StakeManager::CAL_BASE as u128 + 2;
+
+            prop_assert!(fee_value <= expected);
+            prop_assert!(expected - fee_value <= tolerance);
+        }
+    }
+
+    #[test]
+    fn no_fee_without_reward_or_supply() {
+        let stake_manager = stake_manager(
+            StakeManager::DEFAULT_RATE,
+            StakeManager::DEFAULT_PLATFORM_FEE_COMMISSION,
+        );
+
+        assert_eq!(stake_manager.calc_platform_fee(0, 100, 100).unwrap(), 0);
+        assert_eq!(stake_manager.calc_platform_fee(100, 100, 0).unwrap(), 0);
+    }
+
+    #[test]
+    fn unstake_counters_follow_queue() {
+        let mut stake_manager = stake_manager(StakeManager::CAL_BASE, 0);
+        let mut unstake_account = UnstakeAccount {
+            stake_manager: Pubkey::default(),
+            recipient: Pubkey::default(),
+            amount: 100,
+            created_epoch: 0,
+            queue_position: stake_manager.add_unstake(100),
+            rent_payer: Pubkey::default(),
+            era: stake_manager.unstake_funding_era(),
+        };
+        assert_eq!(stake_manager.total_unstake_pending, 100);
+
+        // 60 of the pool is reserved, the rest is free to bond
+        stake_manager.update_unstake_fulfilled(60);
+        assert_eq!(stake_manager.total_unstake_claimable, 60);
+        assert_eq!(stake_manager.calc_bondable(80), 20);
+
+        stake_manager.claim_unstake(&mut unstake_account, 60);
+        assert_eq!(stake_manager.total_unstake_pending, 40);
+        assert_eq!(stake_manager.total_unstake_claimable, 0);
+    }
+
+    #[test]
+    fn unstake_is_funded_after_its_splits_are_withdrawn() {
+        let mut stake_manager = stake_manager(StakeManager::CAL_BASE, 0);
+        stake_manager.latest_era = 10;
+        let era = stake_manager.unstake_funding_era();
+        assert!(!stake_manager.is_era_funded(era));
+        assert_eq!(stake_manager.calc_unstake_claim_epoch(era, 10), 12);
+
+        // era 11 unbonds into a split account deactivated at epoch 12
+        stake_manager.latest_era = 11;
+        stake_manager.unbonding_splits.push(UnbondingSplit {
+            split_account: Pubkey::new_unique(),
+            era,
+            deactivation_epoch: 12,
+        });
+        assert!(!stake_manager.is_era_funded(era));
+        assert_eq!(stake_manager.calc_unstake_claim_epoch(era, 12), 13);
+
+        stake_manager.unbonding_splits.clear();
+        assert!(stake_manager.is_era_funded(era));
+        assert_eq!(stake_manager.calc_unstake_claim_epoch(era, 13), 13);
+    }
+
+    #[test]
+    fn unbonding_duration_is_a_minimum_delay() {
+        let mut stake_manager = stake_manager(StakeManager::CAL_BASE, 0);
+        stake_manager.unbonding_duration = 3;
+        assert_eq!(stake_manager.calc_unstake_min_epoch(10), 13);
+    }
+
+    #[test]
+    fn catch_up_is_capped() {
+        let mut stake_manager = stake_manager(StakeManager::CAL_BASE, 0);
+        stake_manager.latest_era = 5;
+        assert_eq!(stake_manager.calc_catch_up_era(8), 8);
+        assert_eq!(
+            stake_manager.calc_catch_up_era(100),
+            5 + StakeManager::MAX_CATCH_UP_EPOCHS
+        );
+    }
+
+    #[test]
+    fn catch_up_era_funds_every_era_it_covers() {
+        let mut stake_manager = stake_manager(StakeManager::CAL_BASE, 0);
+        stake_manager.latest_era = 5;
+        let era = stake_manager.unstake_funding_era();
+
+        // era 5 catches up to era 10 and unbonds the unstakes of eras 6 to 10
+        stake_manager.latest_era = 10;
+        stake_manager.era_epochs = 5;
+        stake_manager.era_process_data.need_unbond = 100;
+        assert_eq!(stake_manager.latest_era_start(), 6);
+        assert!(stake_manager.is_era_funded(5));
+        assert!(!stake_manager.is_era_funded(era));
+
+        stake_manager.era_process_data.need_unbond = 0;
+        stake_manager.unbonding_splits.push(UnbondingSplit {
+            split_account: Pubkey::new_unique(),
+            era: stake_manager.latest_era_start(),
+            deactivation_epoch: 10,
+        });
+        assert!(!stake_manager.is_era_funded(era));
+        assert_eq!(stake_manager.calc_unstake_claim_epoch(era, 10), 11);
+
+        stake_manager.unbonding_splits.clear();
+        assert!(stake_manager.is_era_funded(era));
+    }
+
+    #[test]
+    fn crank_reward_is_capped_by_budget() {
+        let mut stake_manager = stake_manager(StakeManager::CAL_BASE, 0);
+        stake_manager.crank_reward = 10;
+        stake_manager.crank_budget = 15;
+        let mut cranker_account = CrankerAccount {
+            stake_manager: Pubkey::default(),
+            cranker: Pubkey::default(),
+            bump: 0,
+            unclaimed_reward: 0,
+        };
+
+        assert_eq!(stake_manager.pay_crank_reward(&mut cranker_account), 10);
+        assert_eq!(stake_manager.pay_crank_reward(&mut cranker_account), 5);
+        assert_eq!(stake_manager.pay_crank_reward(&mut cranker_account), 0);
+        assert_eq!(cranker_account.unclaimed_reward, 15);
+        assert_eq!(stake_manager.crank_budget, 0);
+    }
+
+    #[test]
+    fn crank_reward_is_bounded() {
+        assert!(StakeManager::check_crank_reward(StakeManager::MAX_CRANK_REWARD).is_ok());
+        assert!(StakeManager::check_crank_reward(StakeManager::MAX_CRANK_REWARD + 1).is_err());
+    }
+
+    #[test]
+    fn rent_payer_is_recorded_unless_rent_as_yield() {
+        let mut stake_manager = stake_manager(StakeManager::CAL_BASE, 0);
+        let (stake_account, payer) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        stake_manager
+            .add_rent_payer(stake_account, payer, 100, usize::MAX)
+            .unwrap();
+        assert_eq!(stake_manager.refundable_rent(), 100);
+        let rent_payer = stake_manager.take_rent_payer(&stake_account).unwrap();
+        assert_eq!(rent_payer.payer, payer);
+        assert_eq!(rent_payer.rent, 100);
+        assert!(stake_manager.take_rent_payer(&stake_account).is_none());
+
+        stake_manager.rent_as_yield = true;
+        stake_manager
+            .add_rent_payer(stake_account, payer, 100, usize::MAX)
+            .unwrap();
+        assert!(stake_manager.rent_payers.is_empty());
+    }
+
+    #[test]
+    fn rent_payers_are_capped_by_account_limits() {
+        let mut stake_manager = stake_manager(StakeManager::CAL_BASE, 0);
+        stake_manager.stake_accounts_len_limit = 1;
+        stake_manager.split_accounts_len_limit = 1;
+        let payer = Pubkey::new_unique();
+
+        assert!(stake_manager
+            .add_rent_payer(Pubkey::new_unique(), payer, 100, usize::MAX)
+            .is_ok());
+        assert!(stake_manager
+            .add_rent_payer(Pubkey::new_unique(), payer, 100, usize::MAX)
+            .is_ok());
+        assert!(stake_manager
+            .add_rent_payer(Pubkey::new_unique(), payer, 100, usize::MAX)
+            .is_err());
+    }
+
+    #[test]
+    fn rent_payer_is_not_recorded_without_room() {
+        let mut stake_manager = stake_manager(StakeManager::CAL_BASE, 0);
+        let payer = Pubkey::new_unique();
+        let account_len = 8 + stake_manager.try_to_vec().unwrap().len() + 72;
+
+        stake_manager
+            .add_rent_payer(Pubkey::new_unique(), payer, 100, account_len)
+            .unwrap();
+        stake_manager
+            .add_rent_payer(Pubkey::new_unique(), payer, 100, account_len)
+            .unwrap();
+        assert_eq!(stake_manager.rent_payers.len(), 1);
+        assert_eq!(stake_manager.refundable_rent(), 100);
+    }
+
+    #[test]
+    fn rate_change_limit_depends_on_direction() {
+        let mut stake_manager = stake_manager(StakeManager::CAL_BASE, 0);
+        stake_manager.rate_change_limit = 500;
+        let rate = stake_manager.rate;
+
+        assert_eq!(stake_manager.rate_change_limit_of(rate + 1), 500);
+        assert_eq!(stake_manager.rate_change_limit_of(rate - 1), 500);
+
+        stake_manager.rate_up_limit = 100;
+        stake_manager.rate_down_limit = 1_000;
+        assert_eq!(stake_manager.rate_change_limit_of(rate + 1), 100);
+        assert_eq!(stake_manager.rate_change_limit_of(rate - 1), 1_000);
+    }
+
+    #[test]
+    fn insurance_burn_is_capped_by_buffer() {
+        let mut stake_manager = stake_manager(StakeManager::CAL_BASE, 0);
+        stake_manager.insurance_buffer = 30;
+
+        // loss of 20 is fully absorbed, the rate stays put
+        let burn = stake_manager.calc_insurance_burn(980, 1_000).unwrap();
+        assert_eq!(burn, 20);
+        assert_eq!(
+            stake_manager.calc_rate(980, 1_000 - burn).unwrap(),
+            stake_manager.rate
+        );
+
+        // loss of 50 drains the buffer
+        assert_eq!(stake_manager.calc_insurance_burn(950, 1_000).unwrap(), 30);
+        assert_eq!(stake_manager.calc_insurance_burn(1_000, 1_000).unwrap(), 0);
+    }
+
+    #[test]
+    fn deposit_respects_capacity_and_epoch_cap() {
+        let mut stake_manager = stake_manager(StakeManager::DEFAULT_RATE, 0);
+        stake_manager.max_active = 100;
+        stake_manager.epoch_deposit_cap = 60;
+
+        assert!(stake_manager.add_deposit(60, 1).is_ok());
+        assert!(stake_manager.add_deposit(1, 1).is_err());
+        // the per-epoch cap resets with the epoch
+        assert!(stake_manager.add_deposit(60, 2).is_ok());
+
+        stake_manager.active = 90;
+        assert!(stake_manager.add_deposit(20, 3).is_err());
+        assert!(stake_manager.add_deposit(10, 3).is_ok());
+    }
+
+    #[test]
+    fn governance_signers_must_be_unique_and_reach_threshold() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        assert!(Governance::check_signers(&[a, b], 2).is_ok());
+        assert!(Governance::check_signers(&[a, b], 0).is_err());
+        assert!(Governance::check_signers(&[a, b], 3).is_err());
+        assert!(Governance::check_signers(&[a, a], 1).is_err());
+        assert!(Governance::check_signers(&[], 0).is_err());
+    }
+
+    #[test]
+    fn proposal_needs_threshold_approvals_of_current_signers() {
+        let mut governance = Governance {
+            signers: vec![
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+            ],
+            threshold: 2,
+            signer_bump: 0,
+            signers_seqno: 0,
+        };
+        let mut proposal = Proposal {
+            governance: Pubkey::default(),
+            proposer: governance.signers[0],
+            accounts: vec![],
+            data: vec![],
+            approvals: vec![true, false, false],
+            signers_seqno: 0,
+            executed: false,
+            cancelled: false,
+            rent_payer: Pubkey::default(),
+        };
+        assert!(!proposal.approved(&governance));
+
+        proposal.approvals[2] = true;
+        assert!(proposal.approved(&governance));
+        assert!(!proposal.closable(&governance));
+
+        governance.signers_seqno += 1;
+        assert!(!proposal.approved(&governance));
+        assert!(proposal.closable(&governance));
+    }
 }
@@ -0,0 +1,130 @@
+use crate::{Errors, StakeManager};
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    stake::StakeAccount,
+    token::{Mint, TokenAccount},
+};
+
+#[derive(Accounts)]
+pub struct VerifyInvariants<'info> {
+    #[account(has_one = lsd_token_mint @ Errors::MintAccountNotMatch)]
+    pub stake_manager: Box<Account<'info, StakeManager>>,
+
+    #[account(
+        seeds = [
+            &stake_manager.key().to_bytes(),
+            StakeManager::POOL_SEED
+        ],
+        bump = stake_manager.pool_seed_bump
+    )]
+    pub stake_pool: SystemAccount<'info>,
+
+    pub lsd_token_mint: Box<Account<'info, Mint>>,
+
+    #[account(
+        associated_token::mint = stake_manager.lsd_token_mint,
+        associated_token::authority = stake_pool,
+    )]
+    pub referral_fee_escrow: Box<Account<'info, TokenAccount>>,
+}
+
+#[event]
+pub struct EventVerifyInvariants {
+    pub era: u64,
+    pub pool_balance: u64,
+    pub delegated_stake: u64,
+    pub unbonding_stake: u64,
+    pub active: u64,
+    pub unstake_pending: u64,
+    pub refundable_rent: u64,
+    pub surplus: u64,
+    pub deficit: u64,
+    pub lsd_token_supply: u64,
+    pub pending_fee: u64,
+    pub escrow_amount: u64,
+    pub escrowed: u64,
+}
+
+impl<'info> VerifyInvariants<'info> {
+    // remaining accounts: every stake account and split account of the stake manager
+    pub fn process(&mut self, remaining_accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+        require_eq!(
+            remaining_accounts.len(),
+            self.stake_manager.stake_accounts.len() + self.stake_manager.split_accounts.len(),
+            Errors::RemainingAccountsNotMatch
+        );
+
+        // rent of stake and split accounts backs nothing, it goes back to its payer or
+        // turns into reward once the account is withdrawn
+        let mut delegated_stake: u64 = 0;
+        let mut unbonding_stake: u64 = 0;
+        for (i, account_info) in remaining_accounts.iter().enumerate() {
+            require!(
+                !remaining_accounts[..i]
+                    .iter()
+                    .any(|e| e.key == account_info.key),
+                Errors::RemainingAccountsNotMatch
+            );
+
+            let stake_account = Account::<StakeAccount>::try_from(account_info)?;
+            if self.stake_manager.stake_accounts.contains(account_info.key) {
+                let delegation = stake_account
+                    .delegation()
+                    .ok_or_else(|| error!(Errors::DelegationEmpty))?;
+                delegated_stake += delegation.stake;
+            } else if self.stake_manager.split_accounts.contains(account_info.key) {
+                let rent_exempt_reserve = stake_account
+                    .meta()
+                    .ok_or_else(|| error!(Errors::StakeAccountNotExist))?
+                    .rent_exempt_reserve;
+                unbonding_stake += account_info.lamports().saturating_sub(rent_exempt_reserve);
+            } else {
+                return err!(Errors::RemainingAccountsNotMatch);
+            }
+        }
+
+        let pool_balance = self
+            .stake_pool
+            .lamports()
+            .saturating_sub(self.stake_manager.rent_exempt_for_pool_acc);
+
+        // unstakes are paid from the pool and the unbonding split accounts first,
+        // whatever is left of them plus the delegated stake backs active
+        let active = self.stake_manager.active;
+        let unstake_pending = self.stake_manager.total_unstake_pending;
+        let unbonded = pool_balance + unbonding_stake;
+        let unreserved = unbonded.saturating_sub(unstake_pending);
+        let assets = delegated_stake + unreserved;
+        let liabilities = active + unstake_pending.saturating_sub(unbonded);
+
+        let surplus = assets.saturating_sub(liabilities);
+        let deficit = liabilities.saturating_sub(assets);
+
+        // escrowed lsd tokens are already minted, they must sit in the escrow and
+        // can never exceed the supply
+        let lsd_token_supply = self.lsd_token_mint.supply;
+        let escrow_amount = self.referral_fee_escrow.amount;
+        let escrowed = self.stake_manager.insurance_buffer + self.stake_manager.crank_budget;
+
+        emit!(EventVerifyInvariants {
+            era: self.stake_manager.latest_era,
+            pool_balance,
+            delegated_stake,
+            unbonding_stake,
+            active,
+            unstake_pending,
+            refundable_rent: self.stake_manager.refundable_rent(),
+            surplus,
+            deficit,
+            lsd_token_supply,
+            pending_fee: self.stake_manager.pending_fee,
+            escrow_amount,
+            escrowed
+        });
+
+        require_eq!(deficit, 0, Errors::PoolInsolvent);
+        require_gte!(escrow_amount, escrowed, Errors::EscrowInsufficient);
+        require_gte!(lsd_token_supply, escrow_amount, Errors::EscrowInsufficient);
+        Ok(())
+    }
+}
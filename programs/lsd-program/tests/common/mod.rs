@@ -0,0 +1,828 @@
+#![allow(dead_code)]
+
+use anchor_lang::{
+    solana_program::{
+        entrypoint::ProgramResult,
+        instruction::{AccountMeta, Instruction},
+        program_pack::Pack,
+    },
+    AccountDeserialize, AccountSerialize, AnchorDeserialize, InstructionData, ToAccountMetas,
+};
+use anchor_spl::{associated_token::get_associated_token_address, token::spl_token};
+use lsd_program::{accounts, ReferredAccount, StakeManager, UnstakeNonceAccount};
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::{Account, AccountSharedData},
+    account_info::AccountInfo,
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::Keypair,
+    signer::Signer,
+    stake::{
+        self,
+        stake_flags::StakeFlags,
+        state::{Authorized, Delegation, Lockup, Meta, Stake, StakeStateV2},
+    },
+    system_instruction, system_program,
+    sysvar::{self, clock::Clock},
+    transaction::{Transaction, TransactionError},
+    vote::{
+        self,
+        state::{VoteInit, VoteState},
+    },
+};
+
+pub const STAKE_MANAGER_SIZE: usize = 10240;
+
+// anchor ties the accounts slice to the account lifetime, program-test does not
+fn process_instruction<'a, 'b, 'c>(
+    program_id: &'a Pubkey,
+    accounts: &'b [AccountInfo<'c>],
+    data: &[u8],
+) -> ProgramResult {
+    let accounts: &'c [AccountInfo<'c>] = unsafe { std::mem::transmute(accounts) };
+    lsd_program::entry(program_id, accounts, data)
+}
+
+pub fn program_test() -> ProgramTest {
+    // runs the built program instead when tests are run by cargo test-sbf
+    ProgramTest::new(
+        "lsd_program",
+        lsd_program::ID,
+        processor!(process_instruction),
+    )
+}
+
+pub fn instruction(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: lsd_program::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+pub fn transaction(
+    context: &ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Transaction {
+    let mut all_signers = vec![&context.payer];
+    all_signers.extend_from_slice(signers);
+    Transaction::new_signed_with_payer(
+        instructions,
+        Some(&context.payer.pubkey()),
+        &all_signers,
+        context.last_blockhash,
+    )
+}
+
+// signatures are prefixed by a one byte short vec length while there are fewer than 128
+pub fn transaction_size(transaction: &Transaction) -> usize {
+    1 + transaction.signatures.len() * 64 + transaction.message.serialize().len()
+}
+
+pub async fn process(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+    let transaction = transaction(context, instructions, signers);
+    context.banks_client.process_transaction(transaction).await
+}
+
+pub async fn get_account<T: AccountDeserialize>(
+    context: &mut ProgramTestContext,
+    address: Pubkey,
+) -> T {
+    let account = context
+        .banks_client
+        .get_account(address)
+        .await
+        .unwrap()
+        .unwrap();
+    T::try_deserialize(&mut account.data.as_ref()).unwrap()
+}
+
+pub async fn get_lamports(context: &mut ProgramTestContext, address: Pubkey) -> u64 {
+    context
+        .banks_client
+        .get_account(address)
+        .await
+        .unwrap()
+        .map(|e| e.lamports)
+        .unwrap_or_default()
+}
+
+pub async fn account_exists(context: &mut ProgramTestContext, address: Pubkey) -> bool {
+    context
+        .banks_client
+        .get_account(address)
+        .await
+        .unwrap()
+        .is_some()
+}
+
+pub fn assert_error(result: Result<(), BanksClientError>, error: impl Into<u32>) {
+    match result.unwrap_err().unwrap() {
+        TransactionError::InstructionError(_, InstructionError::Custom(code)) => {
+            assert_eq!(code, error.into())
+        }
+        e => panic!("unexpected error {:?}", e),
+    }
+}
+
+// compute units of the transaction, only meaningful when the built program is loaded
+#[cfg(feature = "test-sbf")]
+pub async fn units_consumed(
+    context: &mut ProgramTestContext,
+    instructions: &[Instruction],
+    signers: &[&Keypair],
+) -> u64 {
+    let transaction = transaction(context, instructions, signers);
+    let simulation = context
+        .banks_client
+        .simulate_transaction(transaction)
+        .await
+        .unwrap();
+    simulation.result.unwrap().unwrap();
+    simulation.simulation_details.unwrap().units_consumed
+}
+
+// return value of a view instruction
+pub async fn view<T: AnchorDeserialize>(
+    context: &mut ProgramTestContext,
+    instruction: Instruction,
+) -> T {
+    let transaction = transaction(context, &[instruction], &[]);
+    let simulation = context
+        .banks_client
+        .simulate_transaction(transaction)
+        .await
+        .unwrap();
+    simulation.result.unwrap().unwrap();
+    let return_data = simulation.simulation_details.unwrap().return_data.unwrap();
+    T::try_from_slice(&return_data.data).unwrap()
+}
+
+pub async fn set_account<T: AccountSerialize>(
+    context: &mut ProgramTestContext,
+    address: Pubkey,
+    value: &T,
+) {
+    let mut account = context
+        .banks_client
+        .get_account(address)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut data = vec![];
+    value.try_serialize(&mut data).unwrap();
+    account.data[..data.len()].copy_from_slice(&data);
+    context.set_account(&address, &account.into());
+}
+
+pub async fn set_mint_supply(context: &mut ProgramTestContext, mint: Pubkey, supply: u64) {
+    let mut account = context
+        .banks_client
+        .get_account(mint)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut state = spl_token::state::Mint::unpack(&account.data).unwrap();
+    state.supply = supply;
+    spl_token::state::Mint::pack(state, &mut account.data).unwrap();
+    context.set_account(&mint, &account.into());
+}
+
+// an active stake account delegated to voter, withdrawable by the stake pool
+pub fn add_stake_account(
+    context: &mut ProgramTestContext,
+    stake_pool: Pubkey,
+    voter: Pubkey,
+    stake_amount: u64,
+) -> Pubkey {
+    let address = Pubkey::new_unique();
+    let rent_exempt_reserve = Rent::default().minimum_balance(StakeStateV2::size_of());
+    let state = StakeStateV2::Stake(
+        Meta {
+            rent_exempt_reserve,
+            authorized: Authorized::auto(&stake_pool),
+            lockup: Lockup::default(),
+        },
+        Stake {
+            delegation: Delegation::new(&voter, stake_amount, 0),
+            credits_observed: 0,
+        },
+        StakeFlags::empty(),
+    );
+    let account = Account::new_data_with_space(
+        rent_exempt_reserve + stake_amount,
+        &state,
+        StakeStateV2::size_of(),
+        &stake::program::ID,
+    )
+    .unwrap();
+    context.set_account(&address, &AccountSharedData::from(account));
+    address
+}
+
+pub struct Pool {
+    pub admin: Keypair,
+    pub stack: Pubkey,
+    pub stake_manager: Pubkey,
+    pub stake_pool: Pubkey,
+    pub lsd_token_mint: Pubkey,
+    pub stack_fee_account: Pubkey,
+    pub stack_fee_escrow: Pubkey,
+    pub referral_fee_escrow: Pubkey,
+    pub platform_fee_recipient: Pubkey,
+    pub validator: Pubkey,
+}
+
+impl Pool {
+    pub fn token_account(&self, owner: &Pubkey) -> Pubkey {
+        get_associated_token_address(owner, &self.lsd_token_mint)
+    }
+
+    // tickets are derived from the unstaking owner, or the sender of a transferred ticket
+    pub fn unstake_account(&self, owner: &Pubkey, nonce: u64) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                &self.stake_manager.to_bytes(),
+                &owner.to_bytes(),
+                nonce.to_le_bytes().as_ref(),
+            ],
+            &lsd_program::ID,
+        )
+        .0
+    }
+
+    pub fn unstake_nonce_account(&self, owner: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                &self.stake_manager.to_bytes(),
+                &owner.to_bytes(),
+                UnstakeNonceAccount::SEED,
+            ],
+            &lsd_program::ID,
+        )
+        .0
+    }
+
+    pub fn stake(&self, from: &Pubkey, stake_amount: u64) -> Instruction {
+        instruction(
+            self.stake_accounts(from),
+            lsd_program::instruction::Stake { stake_amount },
+        )
+    }
+
+    pub fn stake_accounts(&self, from: &Pubkey) -> accounts::Stake {
+        accounts::Stake {
+            stake_manager: self.stake_manager,
+            stake_pool: self.stake_pool,
+            from: *from,
+            lsd_token_mint: self.lsd_token_mint,
+            mint_to: self.token_account(from),
+            referrer_account: None,
+            referred_account: None,
+            deposit_authority: None,
+            deposit_allowlist_account: None,
+            clock: sysvar::clock::ID,
+            system_program: system_program::ID,
+            token_program: spl_token::ID,
+        }
+    }
+
+    // unstake of owner's lsd token, nonce is the owner's count of earlier unstakes
+    pub fn unstake(&self, owner: &Pubkey, nonce: u64, unstake_amount: u64) -> Instruction {
+        self.unstake_to(owner, nonce, unstake_amount, None, owner)
+    }
+
+    pub fn unstake_to(
+        &self,
+        owner: &Pubkey,
+        nonce: u64,
+        unstake_amount: u64,
+        recipient: Option<Pubkey>,
+        rent_payer: &Pubkey,
+    ) -> Instruction {
+        let burn_lsd_token_from = self.token_account(owner);
+        instruction(
+            accounts::Unstake {
+                stake_manager: self.stake_manager,
+                lsd_token_mint: self.lsd_token_mint,
+                burn_lsd_token_from,
+                burn_lsd_token_authority: *owner,
+                unstake_nonce_account: self.unstake_nonce_account(owner),
+                unstake_account: self.unstake_account(owner, nonce),
+                referred_account: Pubkey::find_program_address(
+                    &[
+                        &self.stake_manager.to_bytes(),
+                        &burn_lsd_token_from.to_bytes(),
+                        ReferredAccount::SEED,
+                    ],
+                    &lsd_program::ID,
+                )
+                .0,
+                referrer_account: None,
+                rent_payer: *rent_payer,
+                system_program: system_program::ID,
+                token_program: spl_token::ID,
+                clock: sysvar::clock::ID,
+                rent: sysvar::rent::ID,
+            },
+            lsd_program::instruction::Unstake {
+                unstake_amount,
+                recipient,
+            },
+        )
+    }
+
+    pub fn withdraw(&self, unstake_account: Pubkey, recipient: Pubkey) -> Instruction {
+        self.withdraw_refund_to(unstake_account, recipient, recipient)
+    }
+
+    pub fn withdraw_refund_to(
+        &self,
+        unstake_account: Pubkey,
+        recipient: Pubkey,
+        rent_payer: Pubkey,
+    ) -> Instruction {
+        instruction(
+            accounts::Withdraw {
+                stake_manager: self.stake_manager,
+                stake_pool: self.stake_pool,
+                unstake_account,
+                recipient,
+                rent_payer,
+                clock: sysvar::clock::ID,
+                system_program: system_program::ID,
+            },
+            lsd_program::instruction::Withdraw {},
+        )
+    }
+
+    // moves the recipient's ticket to its next nonce, the recipient pays the new ticket's rent
+    pub fn transfer_unstake_ticket(
+        &self,
+        unstake_account: Pubkey,
+        recipient: &Pubkey,
+        ticket_rent_payer: Pubkey,
+        nonce: u64,
+        new_recipient: Pubkey,
+    ) -> Instruction {
+        instruction(
+            accounts::TransferUnstakeTicket {
+                unstake_account,
+                recipient: *recipient,
+                ticket_rent_payer,
+                new_unstake_nonce_account: self.unstake_nonce_account(recipient),
+                new_unstake_account: self.unstake_account(recipient, nonce),
+                rent_payer: *recipient,
+                system_program: system_program::ID,
+            },
+            lsd_program::instruction::TransferUnstakeTicket { new_recipient },
+        )
+    }
+
+    pub fn set_paused(&self, paused: bool) -> Instruction {
+        instruction(
+            accounts::SetPaused {
+                stake_manager: self.stake_manager,
+                pauser: self.admin.pubkey(),
+            },
+            lsd_program::instruction::SetPaused { paused },
+        )
+    }
+
+    // remaining accounts: [unstake_account, rent_payer] pairs
+    pub fn withdraw_batch(
+        &self,
+        recipient: Pubkey,
+        unstake_accounts: &[(Pubkey, Pubkey)],
+    ) -> Instruction {
+        let mut withdraw_batch = instruction(
+            accounts::WithdrawBatch {
+                stake_manager: self.stake_manager,
+                stake_pool: self.stake_pool,
+                recipient,
+                clock: sysvar::clock::ID,
+                system_program: system_program::ID,
+            },
+            lsd_program::instruction::WithdrawBatch {},
+        );
+        for (unstake_account, rent_payer) in unstake_accounts {
+            withdraw_batch
+                .accounts
+                .push(AccountMeta::new(*unstake_account, false));
+            withdraw_batch
+                .accounts
+                .push(AccountMeta::new(*rent_payer, false));
+        }
+        withdraw_batch
+    }
+
+    pub fn apply_pending_config(&self) -> Instruction {
+        instruction(
+            accounts::ApplyPendingConfig {
+                stake_manager: self.stake_manager,
+                stack: self.stack,
+                clock: sysvar::clock::ID,
+            },
+            lsd_program::instruction::ApplyPendingConfig {},
+        )
+    }
+
+    pub fn era_new(&self) -> Instruction {
+        instruction(
+            accounts::EraNew {
+                stake_manager: self.stake_manager,
+                stake_pool: self.stake_pool,
+                clock: sysvar::clock::ID,
+                cranker: None,
+                cranker_account: None,
+            },
+            lsd_program::instruction::EraNew {},
+        )
+    }
+
+    pub fn era_new_catch_up(&self) -> Instruction {
+        instruction(
+            accounts::EraNew {
+                stake_manager: self.stake_manager,
+                stake_pool: self.stake_pool,
+                clock: sysvar::clock::ID,
+                cranker: None,
+                cranker_account: None,
+            },
+            lsd_program::instruction::EraNewCatchUp {},
+        )
+    }
+
+    #[allow(deprecated)]
+    pub fn era_bond(&self, rent_payer: &Pubkey, stake_account: &Pubkey) -> Instruction {
+        instruction(
+            accounts::EraBond {
+                stake_manager: self.stake_manager,
+                validator: self.validator,
+                stake_pool: self.stake_pool,
+                stake_account: *stake_account,
+                rent_payer: *rent_payer,
+                clock: sysvar::clock::ID,
+                rent: sysvar::rent::ID,
+                stake_config: stake::config::ID,
+                stake_history: sysvar::stake_history::ID,
+                stake_program: stake::program::ID,
+                system_program: system_program::ID,
+                cranker: None,
+                cranker_account: None,
+            },
+            lsd_program::instruction::EraBond {},
+        )
+    }
+
+    pub fn era_unbond(
+        &self,
+        rent_payer: &Pubkey,
+        from_stake_account: &Pubkey,
+        split_stake_account: &Pubkey,
+    ) -> Instruction {
+        instruction(
+            accounts::EraUnbond {
+                stake_manager: self.stake_manager,
+                stake_pool: self.stake_pool,
+                from_stake_account: *from_stake_account,
+                split_stake_account: *split_stake_account,
+                validator: self.validator,
+                rent_payer: *rent_payer,
+                clock: sysvar::clock::ID,
+                rent: sysvar::rent::ID,
+                stake_history: sysvar::stake_history::ID,
+                stake_program: stake::program::ID,
+                system_program: system_program::ID,
+                cranker: None,
+                cranker_account: None,
+            },
+            lsd_program::instruction::EraUnbond {},
+        )
+    }
+
+    pub fn era_withdraw(
+        &self,
+        stake_account: &Pubkey,
+        rent_refund_to: Option<Pubkey>,
+    ) -> Instruction {
+        instruction(
+            accounts::EraWithdraw {
+                stake_manager: self.stake_manager,
+                stake_pool: self.stake_pool,
+                stake_account: *stake_account,
+                clock: sysvar::clock::ID,
+                stake_history: sysvar::stake_history::ID,
+                stake_program: stake::program::ID,
+                rent_refund_to,
+                cranker: None,
+                cranker_account: None,
+            },
+            lsd_program::instruction::EraWithdraw {},
+        )
+    }
+
+    pub fn era_update_active(&self, stake_account: &Pubkey) -> Instruction {
+        instruction(
+            accounts::EraUpdateActive {
+                stake_manager: self.stake_manager,
+                stake_account: *stake_account,
+                cranker: None,
+                cranker_account: None,
+            },
+            lsd_program::instruction::EraUpdateActive {},
+        )
+    }
+
+    pub fn era_update_rate(&self) -> Instruction {
+        instruction(
+            self.era_update_rate_accounts(),
+            lsd_program::instruction::EraUpdateRate {},
+        )
+    }
+
+    pub fn era_update_rate_accounts(&self) -> accounts::EraUpdateRate {
+        accounts::EraUpdateRate {
+            stake_manager: self.stake_manager,
+            stack: self.stack,
+            stake_pool: self.stake_pool,
+            lsd_token_mint: self.lsd_token_mint,
+            platform_fee_recipient: self.platform_fee_recipient,
+            stack_fee_escrow: self.stack_fee_escrow,
+            referral_fee_escrow: self.referral_fee_escrow,
+            stack_fee_account: self.stack_fee_account,
+            associated_token_program: anchor_spl::associated_token::ID,
+            token_program: spl_token::ID,
+            cranker: None,
+            cranker_account: None,
+        }
+    }
+}
+
+pub async fn get_epoch(context: &mut ProgramTestContext) -> u64 {
+    context
+        .banks_client
+        .get_sysvar::<Clock>()
+        .await
+        .unwrap()
+        .epoch
+}
+
+pub async fn warp_to_next_epoch(context: &mut ProgramTestContext) {
+    let epoch = get_epoch(context).await;
+    context.warp_to_epoch(epoch + 1).unwrap();
+    // stake accounts are locked while epoch rewards are distributed
+    context.warp_forward_force_reward_interval_end().unwrap();
+}
+
+// a vote account created by the vote program, stake can be delegated to it
+pub async fn add_vote_account(context: &mut ProgramTestContext) -> Pubkey {
+    let vote_account = Keypair::new();
+    let node = Keypair::new();
+    let instructions = vote::instruction::create_account_with_config(
+        &context.payer.pubkey(),
+        &vote_account.pubkey(),
+        &VoteInit {
+            node_pubkey: node.pubkey(),
+            authorized_voter: node.pubkey(),
+            authorized_withdrawer: node.pubkey(),
+            commission: 0,
+        },
+        Rent::default().minimum_balance(VoteState::size_of()),
+        vote::instruction::CreateVoteAccountConfig {
+            space: VoteState::size_of() as u64,
+            with_seed: None,
+        },
+    );
+    process(context, &instructions, &[&vote_account, &node])
+        .await
+        .unwrap();
+    vote_account.pubkey()
+}
+
+pub fn create_associated_token_account(payer: Pubkey, owner: Pubkey, mint: Pubkey) -> Instruction {
+    Instruction {
+        program_id: anchor_spl::associated_token::ID,
+        accounts: vec![
+            AccountMeta::new(payer, true),
+            AccountMeta::new(get_associated_token_address(&owner, &mint), false),
+            AccountMeta::new_readonly(owner, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(system_program::ID, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+        ],
+        data: vec![],
+    }
+}
+
+// a stack and a stake manager set up by the program's own instructions
+pub async fn setup_pool(context: &mut ProgramTestContext) -> Pool {
+    let rent = Rent::default();
+    let payer = context.payer.pubkey();
+    let admin = Keypair::new();
+    let stack = Keypair::new();
+    let stake_manager = Keypair::new();
+    let lsd_token_mint = Keypair::new();
+    let validator = add_vote_account(context).await;
+
+    let (stake_pool, _) = Pubkey::find_program_address(
+        &[&stake_manager.pubkey().to_bytes(), StakeManager::POOL_SEED],
+        &lsd_program::ID,
+    );
+    let (stack_fee_account, _) = Pubkey::find_program_address(
+        &[
+            &stack.pubkey().to_bytes(),
+            &lsd_token_mint.pubkey().to_bytes(),
+        ],
+        &lsd_program::ID,
+    );
+    let stack_fee_escrow =
+        get_associated_token_address(&stack_fee_account, &lsd_token_mint.pubkey());
+    let referral_fee_escrow = get_associated_token_address(&stake_pool, &lsd_token_mint.pubkey());
+    let platform_fee_recipient =
+        get_associated_token_address(&admin.pubkey(), &lsd_token_mint.pubkey());
+
+    let instructions = [
+        instruction(
+            accounts::InitializeStack {
+                stack: stack.pubkey(),
+                rent_payer: payer,
+                admin: admin.pubkey(),
+                system_program: system_program::ID,
+            },
+            lsd_program::instruction::InitializeStack {},
+        ),
+        system_instruction::create_account(
+            &payer,
+            &lsd_token_mint.pubkey(),
+            rent.minimum_balance(spl_token::state::Mint::LEN),
+            spl_token::state::Mint::LEN as u64,
+            &spl_token::ID,
+        ),
+        spl_token::instruction::initialize_mint(
+            &spl_token::ID,
+            &lsd_token_mint.pubkey(),
+            &stake_pool,
+            None,
+            9,
+        )
+        .unwrap(),
+        system_instruction::transfer(&payer, &stake_pool, rent.minimum_balance(0)),
+        system_instruction::create_account(
+            &payer,
+            &stake_manager.pubkey(),
+            rent.minimum_balance(STAKE_MANAGER_SIZE),
+            STAKE_MANAGER_SIZE as u64,
+            &lsd_program::ID,
+        ),
+    ];
+    process(
+        context,
+        &instructions,
+        &[&admin, &stack, &lsd_token_mint, &stake_manager],
+    )
+    .await
+    .unwrap();
+
+    let instructions = [
+        instruction(
+            accounts::InitializeStakeManager {
+                stake_manager: stake_manager.pubkey(),
+                stack: stack.pubkey(),
+                stake_pool,
+                stack_fee_account,
+                stack_fee_escrow,
+                referral_fee_escrow,
+                lsd_token_mint: lsd_token_mint.pubkey(),
+                validator,
+                rent_payer: payer,
+                admin: admin.pubkey(),
+                associated_token_program: anchor_spl::associated_token::ID,
+                token_program: spl_token::ID,
+                system_program: system_program::ID,
+                clock: sysvar::clock::ID,
+                rent: sysvar::rent::ID,
+            },
+            lsd_program::instruction::InitializeStakeManager {},
+        ),
+        create_associated_token_account(payer, admin.pubkey(), lsd_token_mint.pubkey()),
+    ];
+    process(context, &instructions, &[&admin]).await.unwrap();
+
+    Pool {
+        admin,
+        stack: stack.pubkey(),
+        stake_manager: stake_manager.pubkey(),
+        stake_pool,
+        lsd_token_mint: lsd_token_mint.pubkey(),
+        stack_fee_account,
+        stack_fee_escrow,
+        referral_fee_escrow,
+        platform_fee_recipient,
+        validator,
+    }
+}
+
+// a funded staker with an lsd token account
+pub async fn add_staker(context: &mut ProgramTestContext, pool: &Pool, lamports: u64) -> Keypair {
+    let staker = Keypair::new();
+    let payer = context.payer.pubkey();
+    let instructions = [
+        system_instruction::transfer(&payer, &staker.pubkey(), lamports),
+        create_associated_token_account(payer, staker.pubkey(), pool.lsd_token_mint),
+    ];
+    process(context, &instructions, &[]).await.unwrap();
+    staker
+}
+
+pub async fn get_token_amount(context: &mut ProgramTestContext, address: Pubkey) -> u64 {
+    let account = context
+        .banks_client
+        .get_account(address)
+        .await
+        .unwrap()
+        .unwrap();
+    spl_token::state::Account::unpack(&account.data)
+        .unwrap()
+        .amount
+}
+
+// stake rewards of an epoch, credited to the delegation like the runtime does
+pub async fn add_stake_reward(
+    context: &mut ProgramTestContext,
+    stake_account: Pubkey,
+    reward: u64,
+) {
+    let payer = context.payer.pubkey();
+    process(
+        context,
+        &[system_instruction::transfer(&payer, &stake_account, reward)],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let mut account = context
+        .banks_client
+        .get_account(stake_account)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut state: StakeStateV2 = account.deserialize_data().unwrap();
+    if let StakeStateV2::Stake(_, stake, _) = &mut state {
+        stake.delegation.stake += reward;
+    }
+    account.serialize_data(&state).unwrap();
+    context.set_account(&stake_account, &account.into());
+}
+
+// slashes the delegated stake, lamports stay so the bank's capitalization doesn't change
+pub async fn add_stake_loss(context: &mut ProgramTestContext, stake_account: Pubkey, loss: u64) {
+    let mut account = context
+        .banks_client
+        .get_account(stake_account)
+        .await
+        .unwrap()
+        .unwrap();
+    let mut state: StakeStateV2 = account.deserialize_data().unwrap();
+    if let StakeStateV2::Stake(_, stake, _) = &mut state {
+        stake.delegation.stake -= loss;
+    }
+    account.serialize_data(&state).unwrap();
+    context.set_account(&stake_account, &account.into());
+}
+
+pub async fn get_mint_supply(context: &mut ProgramTestContext, mint: Pubkey) -> u64 {
+    let account = context
+        .banks_client
+        .get_account(mint)
+        .await
+        .unwrap()
+        .unwrap();
+    spl_token::state::Mint::unpack(&account.data)
+        .unwrap()
+        .supply
+}
+
+// moves to the next epoch and runs an era that bonds the deposits into a new stake account
+pub async fn bond_era(context: &mut ProgramTestContext, pool: &Pool) -> Pubkey {
+    warp_to_next_epoch(context).await;
+    let stake_account = Keypair::new();
+    let instructions = [
+        pool.era_new(),
+        pool.era_bond(&context.payer.pubkey(), &stake_account.pubkey()),
+        pool.era_update_active(&stake_account.pubkey()),
+        pool.era_update_rate(),
+    ];
+    process(context, &instructions, &[&stake_account])
+        .await
+        .unwrap();
+    stake_account.pubkey()
+}
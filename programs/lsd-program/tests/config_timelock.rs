@@ -0,0 +1,133 @@
+mod common;
+
+use common::*;
+use lsd_program::{accounts, instruction, Errors, StakeManager};
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, signer::Signer, sysvar};
+
+const STAKE_AMOUNT: u64 = 10 * LAMPORTS_PER_SOL;
+
+#[tokio::test]
+async fn stack_fee_commission_waits_for_the_timelock() {
+    let mut context = program_test().start_with_context().await;
+    let pool = setup_pool(&mut context).await;
+    let admin = pool.admin.pubkey();
+
+    let staker = add_staker(&mut context, &pool, STAKE_AMOUNT + LAMPORTS_PER_SOL).await;
+    process(
+        &mut context,
+        &[pool.stake(&staker.pubkey(), STAKE_AMOUNT)],
+        &[&staker],
+    )
+    .await
+    .unwrap();
+
+    // the stack default changes at once but only the timelocked commission is charged
+    let instructions = [
+        common::instruction(
+            accounts::SetStackFeeCommission {
+                stack: pool.stack,
+                admin,
+            },
+            instruction::SetStackFeeCommission {
+                stack_fee_commission: StakeManager::CAL_BASE / 2,
+            },
+        ),
+        common::instruction(
+            accounts::SetPlatformStackFeeCommission {
+                stake_manager: pool.stake_manager,
+                stack: pool.stack,
+                admin,
+                clock: sysvar::clock::ID,
+            },
+            instruction::SetPlatformStackFeeCommission {
+                stack_fee_commission: 0,
+            },
+        ),
+    ];
+    process(&mut context, &instructions, &[&pool.admin])
+        .await
+        .unwrap();
+    assert_error(
+        process(&mut context, &[pool.apply_pending_config()], &[]).await,
+        Errors::PendingConfigNotReady,
+    );
+
+    let stake_account = bond_era(&mut context, &pool).await;
+    add_stake_reward(&mut context, stake_account, STAKE_AMOUNT / 2_500).await;
+    warp_to_next_epoch(&mut context).await;
+    let instructions = [
+        pool.era_new(),
+        pool.era_update_active(&stake_account),
+        pool.era_update_rate(),
+    ];
+    process(&mut context, &instructions, &[]).await.unwrap();
+
+    let stack_fee = get_token_amount(&mut context, pool.stack_fee_escrow).await;
+    let platform_fee = get_token_amount(&mut context, pool.platform_fee_recipient).await;
+    assert!(stack_fee > 0);
+    assert_eq!((stack_fee + platform_fee) / 10, stack_fee);
+
+    process(&mut context, &[pool.apply_pending_config()], &[])
+        .await
+        .unwrap();
+    let stake_manager: StakeManager = get_account(&mut context, pool.stake_manager).await;
+    assert_eq!(stake_manager.stack_fee_commission, 0);
+    assert!(stake_manager.pending_configs.is_empty());
+}
+
+#[tokio::test]
+async fn apply_rejects_values_over_the_current_stack_cap() {
+    let mut context = program_test().start_with_context().await;
+    let pool = setup_pool(&mut context).await;
+    let admin = pool.admin.pubkey();
+
+    let set_deposit_fee_bps = |deposit_fee_bps| {
+        common::instruction(
+            accounts::SetDepositFeeBps {
+                stake_manager: pool.stake_manager,
+                stack: pool.stack,
+                authority: admin,
+                clock: sysvar::clock::ID,
+            },
+            instruction::SetDepositFeeBps { deposit_fee_bps },
+        )
+    };
+    let set_max_fee_bps = common::instruction(
+        accounts::SetMaxFeeBps {
+            stack: pool.stack,
+            admin,
+        },
+        instruction::SetMaxFeeBps {
+            max_deposit_fee_bps: 50,
+            max_unstake_fee_bps: 50,
+        },
+    );
+
+    // queued under the old cap, the stack admin lowers it before the timelock passes
+    process(&mut context, &[set_deposit_fee_bps(100)], &[&pool.admin])
+        .await
+        .unwrap();
+    process(&mut context, &[set_max_fee_bps], &[&pool.admin])
+        .await
+        .unwrap();
+    for _ in 0..StakeManager::DEFAULT_CONFIG_TIMELOCK_EPOCHS {
+        warp_to_next_epoch(&mut context).await;
+    }
+    assert_error(
+        process(&mut context, &[pool.apply_pending_config()], &[]).await,
+        Errors::DepositFeeOverLimit,
+    );
+
+    // a value within the new cap replaces it and waits out its own timelock
+    process(&mut context, &[set_deposit_fee_bps(50)], &[&pool.admin])
+        .await
+        .unwrap();
+    for _ in 0..StakeManager::DEFAULT_CONFIG_TIMELOCK_EPOCHS {
+        warp_to_next_epoch(&mut context).await;
+    }
+    process(&mut context, &[pool.apply_pending_config()], &[])
+        .await
+        .unwrap();
+    let stake_manager: StakeManager = get_account(&mut context, pool.stake_manager).await;
+    assert_eq!(stake_manager.deposit_fee_bps, 50);
+}
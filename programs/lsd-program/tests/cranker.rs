@@ -0,0 +1,81 @@
+mod common;
+
+use anchor_lang::error::ErrorCode;
+use common::*;
+use lsd_program::{accounts, CrankerAccount, Errors, StakeManager};
+use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer, system_program, sysvar};
+
+const CRANK_REWARD: u64 = 1_000;
+
+#[tokio::test]
+async fn crank_reward_is_paid_only_to_the_signing_cranker() {
+    let mut context = program_test().start_with_context().await;
+    let pool = setup_pool(&mut context).await;
+    let payer = context.payer.pubkey();
+    let cranker = Keypair::new();
+    let other = Keypair::new();
+
+    let (cranker_account, _) = Pubkey::find_program_address(
+        &[
+            &pool.stake_manager.to_bytes(),
+            &cranker.pubkey().to_bytes(),
+            CrankerAccount::SEED,
+        ],
+        &lsd_program::ID,
+    );
+    process(
+        &mut context,
+        &[instruction(
+            accounts::RegisterCranker {
+                stake_manager: pool.stake_manager,
+                cranker_account,
+                cranker: cranker.pubkey(),
+                rent_payer: payer,
+                system_program: system_program::ID,
+            },
+            lsd_program::instruction::RegisterCranker {},
+        )],
+        &[&cranker],
+    )
+    .await
+    .unwrap();
+
+    let mut stake_manager: StakeManager = get_account(&mut context, pool.stake_manager).await;
+    stake_manager.crank_reward = CRANK_REWARD;
+    stake_manager.crank_budget = 10 * CRANK_REWARD;
+    set_account(&mut context, pool.stake_manager, &stake_manager).await;
+
+    let era_new = |cranker: Option<Pubkey>| {
+        instruction(
+            accounts::EraNew {
+                stake_manager: pool.stake_manager,
+                stake_pool: pool.stake_pool,
+                clock: sysvar::clock::ID,
+                cranker,
+                cranker_account: Some(cranker_account),
+            },
+            lsd_program::instruction::EraNew {},
+        )
+    };
+
+    // naming someone else's cranker account without its signature earns nothing
+    warp_to_next_epoch(&mut context).await;
+    assert_error(
+        process(&mut context, &[era_new(None)], &[]).await,
+        ErrorCode::ConstraintAccountIsNone,
+    );
+    assert_error(
+        process(&mut context, &[era_new(Some(other.pubkey()))], &[&other]).await,
+        Errors::CrankerNotMatch,
+    );
+
+    process(
+        &mut context,
+        &[era_new(Some(cranker.pubkey()))],
+        &[&cranker],
+    )
+    .await
+    .unwrap();
+    let cranker_account: CrankerAccount = get_account(&mut context, cranker_account).await;
+    assert_eq!(cranker_account.unclaimed_reward, CRANK_REWARD);
+}
@@ -0,0 +1,169 @@
+mod common;
+
+use common::*;
+use lsd_program::{accounts, instruction, DepositAllowlistAccount, Errors};
+use solana_sdk::{
+    instruction::Instruction, native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, signature::Keypair,
+    signer::Signer, system_program,
+};
+
+fn set_deposit_authority(pool: &Pool, deposit_authority: Option<Pubkey>) -> Instruction {
+    common::instruction(
+        accounts::SetDepositAuthority {
+            stake_manager: pool.stake_manager,
+            admin: pool.admin.pubkey(),
+        },
+        instruction::SetDepositAuthority { deposit_authority },
+    )
+}
+
+fn deposit_allowlist_account(pool: &Pool, depositor: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            &pool.stake_manager.to_bytes(),
+            &depositor.to_bytes(),
+            DepositAllowlistAccount::SEED,
+        ],
+        &lsd_program::ID,
+    )
+    .0
+}
+
+fn stake(
+    pool: &Pool,
+    from: &Pubkey,
+    stake_amount: u64,
+    deposit_authority: Option<Pubkey>,
+    deposit_allowlist_account: Option<Pubkey>,
+) -> Instruction {
+    common::instruction(
+        accounts::Stake {
+            deposit_authority,
+            deposit_allowlist_account,
+            ..pool.stake_accounts(from)
+        },
+        instruction::Stake { stake_amount },
+    )
+}
+
+#[tokio::test]
+async fn permissioned_pool_takes_cosigned_or_allowlisted_deposits() {
+    let mut context = program_test().start_with_context().await;
+    let pool = setup_pool(&mut context).await;
+    let payer = context.payer.pubkey();
+    let authority = Keypair::new();
+
+    process(
+        &mut context,
+        &[set_deposit_authority(&pool, Some(authority.pubkey()))],
+        &[&pool.admin],
+    )
+    .await
+    .unwrap();
+
+    let first = add_staker(&mut context, &pool, 10 * LAMPORTS_PER_SOL).await;
+    let second = add_staker(&mut context, &pool, 10 * LAMPORTS_PER_SOL).await;
+    assert_error(
+        process(
+            &mut context,
+            &[pool.stake(&first.pubkey(), LAMPORTS_PER_SOL)],
+            &[&first],
+        )
+        .await,
+        Errors::DepositNotAllowed,
+    );
+    process(
+        &mut context,
+        &[stake(
+            &pool,
+            &first.pubkey(),
+            LAMPORTS_PER_SOL,
+            Some(authority.pubkey()),
+            None,
+        )],
+        &[&first, &authority],
+    )
+    .await
+    .unwrap();
+
+    // the allowlisted depositor stakes without the authority
+    let allowlisted = deposit_allowlist_account(&pool, &second.pubkey());
+    process(
+        &mut context,
+        &[common::instruction(
+            accounts::AddDepositAllowlist {
+                stake_manager: pool.stake_manager,
+                deposit_authority: authority.pubkey(),
+                deposit_allowlist_account: allowlisted,
+                rent_payer: payer,
+                system_program: system_program::ID,
+            },
+            instruction::AddDepositAllowlist {
+                depositor: second.pubkey(),
+            },
+        )],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    process(
+        &mut context,
+        &[stake(
+            &pool,
+            &second.pubkey(),
+            LAMPORTS_PER_SOL,
+            None,
+            Some(allowlisted),
+        )],
+        &[&second],
+    )
+    .await
+    .unwrap();
+
+    process(
+        &mut context,
+        &[common::instruction(
+            accounts::RemoveDepositAllowlist {
+                stake_manager: pool.stake_manager,
+                deposit_authority: authority.pubkey(),
+                deposit_allowlist_account: allowlisted,
+                rent_receiver: payer,
+            },
+            instruction::RemoveDepositAllowlist {},
+        )],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    assert!(!account_exists(&mut context, allowlisted).await);
+    assert_error(
+        process(
+            &mut context,
+            &[pool.stake(&second.pubkey(), 2 * LAMPORTS_PER_SOL)],
+            &[&second],
+        )
+        .await,
+        Errors::DepositNotAllowed,
+    );
+
+    // clearing the authority opens the pool again
+    process(
+        &mut context,
+        &[set_deposit_authority(&pool, None)],
+        &[&pool.admin],
+    )
+    .await
+    .unwrap();
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    process(
+        &mut context,
+        &[pool.stake(&second.pubkey(), 2 * LAMPORTS_PER_SOL)],
+        &[&second],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        get_token_amount(&mut context, pool.token_account(&second.pubkey())).await,
+        3 * LAMPORTS_PER_SOL
+    );
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use common::*;
+use lsd_program::{accounts, instruction, Errors, StakeManager};
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, signer::Signer};
+
+#[tokio::test]
+async fn deposits_stop_at_the_pool_capacity_and_the_epoch_cap() {
+    let mut context = program_test().start_with_context().await;
+    let pool = setup_pool(&mut context).await;
+
+    process(
+        &mut context,
+        &[common::instruction(
+            accounts::SetDepositCaps {
+                stake_manager: pool.stake_manager,
+                authority: pool.admin.pubkey(),
+            },
+            instruction::SetDepositCaps {
+                max_active: 5 * LAMPORTS_PER_SOL,
+                epoch_deposit_cap: 3 * LAMPORTS_PER_SOL,
+            },
+        )],
+        &[&pool.admin],
+    )
+    .await
+    .unwrap();
+
+    let staker = add_staker(&mut context, &pool, 10 * LAMPORTS_PER_SOL).await;
+    let stake = |amount| pool.stake(&staker.pubkey(), amount * LAMPORTS_PER_SOL);
+    process(&mut context, &[stake(2)], &[&staker])
+        .await
+        .unwrap();
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    assert_error(
+        process(&mut context, &[stake(2)], &[&staker]).await,
+        Errors::EpochDepositCapExceeded,
+    );
+    process(&mut context, &[stake(1)], &[&staker])
+        .await
+        .unwrap();
+
+    // the epoch cap starts over, the pool capacity doesn't
+    warp_to_next_epoch(&mut context).await;
+    assert_error(
+        process(&mut context, &[stake(3)], &[&staker]).await,
+        Errors::PoolCapacityExceeded,
+    );
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    process(&mut context, &[stake(2)], &[&staker])
+        .await
+        .unwrap();
+
+    let stake_manager: StakeManager = get_account(&mut context, pool.stake_manager).await;
+    assert_eq!(stake_manager.active, 5 * LAMPORTS_PER_SOL);
+    assert_eq!(stake_manager.epoch_deposited, 2 * LAMPORTS_PER_SOL);
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use common::*;
+use lsd_program::{StakeManager, UnstakeAccount};
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, signer::Signer};
+
+const STAKE_AMOUNT: u64 = 10 * LAMPORTS_PER_SOL;
+
+#[tokio::test]
+async fn deposit_and_unstake_fees_are_minted_by_the_next_era() {
+    let mut context = program_test().start_with_context().await;
+    let pool = setup_pool(&mut context).await;
+
+    let mut stake_manager: StakeManager = get_account(&mut context, pool.stake_manager).await;
+    stake_manager.deposit_fee_bps = 100;
+    stake_manager.unstake_fee_bps = 50;
+    set_account(&mut context, pool.stake_manager, &stake_manager).await;
+
+    let staker = add_staker(&mut context, &pool, STAKE_AMOUNT + LAMPORTS_PER_SOL).await;
+    let staker_token_account = pool.token_account(&staker.pubkey());
+    let unstake_amount = LAMPORTS_PER_SOL;
+    let instructions = [
+        pool.stake(&staker.pubkey(), STAKE_AMOUNT),
+        pool.unstake(&staker.pubkey(), 0, unstake_amount),
+    ];
+    process(&mut context, &instructions, &[&staker])
+        .await
+        .unwrap();
+
+    // both fees are kept out of the staker's lsd token and the unstaked sol
+    let deposit_fee = STAKE_AMOUNT / 100;
+    let unstake_fee = unstake_amount / 200;
+    assert_eq!(
+        get_token_amount(&mut context, staker_token_account).await,
+        STAKE_AMOUNT - deposit_fee - unstake_amount
+    );
+    let ticket: UnstakeAccount =
+        get_account(&mut context, pool.unstake_account(&staker.pubkey(), 0)).await;
+    assert_eq!(ticket.amount, unstake_amount - unstake_fee);
+    let stake_manager: StakeManager = get_account(&mut context, pool.stake_manager).await;
+    assert_eq!(stake_manager.pending_fee, deposit_fee + unstake_fee);
+
+    bond_era(&mut context, &pool).await;
+
+    // the era mints them without a reward and without moving the rate
+    let stack_fee = (deposit_fee + unstake_fee) / 10;
+    let platform_fee = deposit_fee + unstake_fee - stack_fee;
+    let stake_manager: StakeManager = get_account(&mut context, pool.stake_manager).await;
+    assert_eq!(stake_manager.pending_fee, 0);
+    assert_eq!(stake_manager.total_platform_fee, platform_fee);
+    assert_eq!(stake_manager.rate, StakeManager::DEFAULT_RATE);
+    assert_eq!(
+        get_token_amount(&mut context, pool.platform_fee_recipient).await,
+        platform_fee
+    );
+    assert_eq!(
+        get_token_amount(&mut context, pool.stack_fee_escrow).await,
+        stack_fee
+    );
+}
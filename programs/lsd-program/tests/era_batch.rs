@@ -0,0 +1,155 @@
+mod common;
+
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use common::*;
+use lsd_program::{accounts, instruction, EraProcessData, Errors, StakeManager};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{packet::PACKET_DATA_SIZE, pubkey::Pubkey};
+
+// compute unit limit of a transaction without a compute budget instruction, native runs
+// of the processor are not metered so the limit is only checked under test-sbf
+#[cfg(feature = "test-sbf")]
+const DEFAULT_COMPUTE_UNIT_LIMIT: u64 = 200_000;
+const STAKE_AMOUNT: u64 = 1_000_000_000_000;
+const REWARD: u64 = STAKE_AMOUNT / 10_000;
+
+// an era waiting for update active on stake_accounts_len active stake accounts, each with a reward
+async fn start_update_active(stake_accounts_len: u64) -> (ProgramTestContext, Pool, Vec<Pubkey>) {
+    let mut context = program_test().start_with_context().await;
+    let pool = setup_pool(&mut context).await;
+
+    let stake_accounts: Vec<Pubkey> = (0..stake_accounts_len)
+        .map(|_| {
+            add_stake_account(
+                &mut context,
+                pool.stake_pool,
+                pool.validator,
+                STAKE_AMOUNT + REWARD,
+            )
+        })
+        .collect();
+
+    let active = STAKE_AMOUNT * stake_accounts_len;
+    let mut stake_manager: StakeManager = get_account(&mut context, pool.stake_manager).await;
+    stake_manager.active = active;
+    stake_manager.stake_accounts = stake_accounts.clone();
+    stake_manager.era_process_data = EraProcessData {
+        need_bond: 0,
+        need_unbond: 0,
+        old_active: active,
+        new_active: 0,
+        pending_stake_accounts: stake_accounts.clone(),
+    };
+    set_account(&mut context, pool.stake_manager, &stake_manager).await;
+    set_mint_supply(&mut context, pool.lsd_token_mint, active).await;
+
+    (context, pool, stake_accounts)
+}
+
+fn with_remaining_accounts(mut instruction: Instruction, stake_accounts: &[Pubkey]) -> Instruction {
+    instruction.accounts.extend(
+        stake_accounts
+            .iter()
+            .map(|e| AccountMeta::new_readonly(*e, false)),
+    );
+    instruction
+}
+
+fn update_active_batch(pool: &Pool, stake_accounts: &[Pubkey]) -> Instruction {
+    with_remaining_accounts(
+        common::instruction(
+            accounts::EraUpdateActiveBatch {
+                stake_manager: pool.stake_manager,
+                cranker: None,
+                cranker_account: None,
+            },
+            instruction::EraUpdateActiveBatch {},
+        ),
+        stake_accounts,
+    )
+}
+
+fn finalize(pool: &Pool, stake_accounts: &[Pubkey]) -> Instruction {
+    with_remaining_accounts(
+        common::instruction(
+            accounts::EraFinalize {
+                era_update_rate: pool.era_update_rate_accounts(),
+            },
+            instruction::EraFinalize {},
+        ),
+        stake_accounts,
+    )
+}
+
+#[tokio::test]
+async fn update_active_batch_limit_fits_one_transaction() {
+    let limit = StakeManager::UPDATE_ACTIVE_BATCH_LIMIT;
+    let (mut context, pool, stake_accounts) = start_update_active(limit + 1).await;
+
+    assert_error(
+        process(
+            &mut context,
+            &[update_active_batch(&pool, &stake_accounts)],
+            &[],
+        )
+        .await,
+        Errors::RemainingAccountsNotMatch,
+    );
+
+    let batch = update_active_batch(&pool, &stake_accounts[..limit as usize]);
+    let size = transaction_size(&transaction(&context, std::slice::from_ref(&batch), &[]));
+    assert!(size <= PACKET_DATA_SIZE);
+    #[cfg(feature = "test-sbf")]
+    assert!(
+        units_consumed(&mut context, std::slice::from_ref(&batch), &[]).await
+            <= DEFAULT_COMPUTE_UNIT_LIMIT
+    );
+
+    process(&mut context, &[batch], &[]).await.unwrap();
+    let stake_manager: StakeManager = get_account(&mut context, pool.stake_manager).await;
+    assert_eq!(
+        stake_manager.era_process_data.pending_stake_accounts,
+        stake_accounts[limit as usize..]
+    );
+    assert_eq!(
+        stake_manager.era_process_data.new_active,
+        (STAKE_AMOUNT + REWARD) * limit
+    );
+}
+
+#[tokio::test]
+async fn finalize_limit_fits_one_transaction() {
+    let limit = StakeManager::FINALIZE_BATCH_LIMIT;
+    let (mut context, pool, stake_accounts) = start_update_active(limit).await;
+
+    let finalize = finalize(&pool, &stake_accounts);
+    let size = transaction_size(&transaction(&context, std::slice::from_ref(&finalize), &[]));
+    assert!(size <= PACKET_DATA_SIZE);
+    #[cfg(feature = "test-sbf")]
+    assert!(
+        units_consumed(&mut context, std::slice::from_ref(&finalize), &[]).await
+            <= DEFAULT_COMPUTE_UNIT_LIMIT
+    );
+
+    process(&mut context, &[finalize], &[]).await.unwrap();
+    let stake_manager: StakeManager = get_account(&mut context, pool.stake_manager).await;
+    assert!(stake_manager
+        .era_process_data
+        .pending_stake_accounts
+        .is_empty());
+    assert_eq!(stake_manager.active, (STAKE_AMOUNT + REWARD) * limit);
+    assert!(stake_manager.rate > StakeManager::DEFAULT_RATE);
+    assert_eq!(stake_manager.era_rates.len(), 1);
+    assert!(get_lamports(&mut context, pool.platform_fee_recipient).await > 0);
+}
+
+#[tokio::test]
+async fn finalize_rejects_more_than_limit() {
+    let limit = StakeManager::FINALIZE_BATCH_LIMIT;
+    let (mut context, pool, stake_accounts) = start_update_active(limit + 1).await;
+
+    assert_error(
+        process(&mut context, &[finalize(&pool, &stake_accounts)], &[]).await,
+        Errors::RemainingAccountsNotMatch,
+    );
+}
@@ -0,0 +1,108 @@
+mod common;
+
+use common::*;
+use lsd_program::{accounts, instruction, Errors, StakeManager};
+use solana_program_test::BanksClientError;
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, signer::Signer};
+
+const STAKE_AMOUNT: u64 = 10 * LAMPORTS_PER_SOL;
+
+#[tokio::test]
+async fn loss_over_the_limit_burns_insurance_and_needs_the_admin() {
+    let mut context = program_test().start_with_context().await;
+    let pool = setup_pool(&mut context).await;
+
+    let staker = add_staker(&mut context, &pool, STAKE_AMOUNT + LAMPORTS_PER_SOL).await;
+    process(
+        &mut context,
+        &[pool.stake(&staker.pubkey(), STAKE_AMOUNT)],
+        &[&staker],
+    )
+    .await
+    .unwrap();
+    let stake_account = bond_era(&mut context, &pool).await;
+
+    // a rewarded era funds the insurance buffer with the platform's share of the fee
+    let mut stake_manager: StakeManager = get_account(&mut context, pool.stake_manager).await;
+    stake_manager.insurance_fee_commission = StakeManager::CAL_BASE;
+    set_account(&mut context, pool.stake_manager, &stake_manager).await;
+    add_stake_reward(&mut context, stake_account, STAKE_AMOUNT / 2_500).await;
+    warp_to_next_epoch(&mut context).await;
+    let instructions = [
+        pool.era_new(),
+        pool.era_update_active(&stake_account),
+        pool.era_update_rate(),
+    ];
+    process(&mut context, &instructions, &[]).await.unwrap();
+    let stake_manager: StakeManager = get_account(&mut context, pool.stake_manager).await;
+    let insurance_buffer = stake_manager.insurance_buffer;
+    let old_rate = stake_manager.rate;
+    assert!(insurance_buffer > 0);
+    assert_eq!(
+        get_token_amount(&mut context, pool.referral_fee_escrow).await,
+        insurance_buffer
+    );
+
+    // a tenth of the stake is slashed, far over the rate change limit
+    add_stake_loss(&mut context, stake_account, STAKE_AMOUNT / 10).await;
+    warp_to_next_epoch(&mut context).await;
+    let instructions = [pool.era_new(), pool.era_update_active(&stake_account)];
+    process(&mut context, &instructions, &[]).await.unwrap();
+    let transaction = transaction(&context, &[pool.era_update_rate()], &[]);
+    let result = context
+        .banks_client
+        .process_transaction_with_metadata(transaction)
+        .await
+        .unwrap();
+    assert!(result
+        .metadata
+        .unwrap()
+        .log_messages
+        .iter()
+        .any(|e| e.starts_with("Program log: RateChangeAlert: era=")));
+    assert_error(
+        result.result.map_err(BanksClientError::TransactionError),
+        Errors::RateChangeOverLimit,
+    );
+
+    // the buffer is burned before the rate drops, the admin acknowledges the new rate
+    let new_active = stake_manager.active - STAKE_AMOUNT / 10;
+    let supply = get_mint_supply(&mut context, pool.lsd_token_mint).await;
+    let new_rate = (new_active as u128 * StakeManager::CAL_BASE as u128
+        / (supply - insurance_buffer) as u128) as u64;
+    let force_era_update_rate = |expected_rate| {
+        common::instruction(
+            accounts::ForceEraUpdateRate {
+                era_update_rate: pool.era_update_rate_accounts(),
+                admin: pool.admin.pubkey(),
+            },
+            instruction::ForceEraUpdateRate { expected_rate },
+        )
+    };
+    assert_error(
+        process(
+            &mut context,
+            &[force_era_update_rate(old_rate)],
+            &[&pool.admin],
+        )
+        .await,
+        Errors::ForceRateNotMatch,
+    );
+    process(
+        &mut context,
+        &[force_era_update_rate(new_rate)],
+        &[&pool.admin],
+    )
+    .await
+    .unwrap();
+
+    let stake_manager: StakeManager = get_account(&mut context, pool.stake_manager).await;
+    assert_eq!(stake_manager.rate, new_rate);
+    assert!(new_rate < old_rate);
+    assert_eq!(stake_manager.active, new_active);
+    assert_eq!(stake_manager.insurance_buffer, 0);
+    assert_eq!(
+        get_token_amount(&mut context, pool.referral_fee_escrow).await,
+        0
+    );
+}
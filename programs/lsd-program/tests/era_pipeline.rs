@@ -0,0 +1,153 @@
+mod common;
+
+use common::*;
+use lsd_program::StakeManager;
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, signature::Keypair, signer::Signer};
+
+const STAKE_AMOUNT: u64 = 10 * LAMPORTS_PER_SOL;
+
+async fn fee_minted(context: &mut ProgramTestContext, pool: &Pool) -> u64 {
+    get_token_amount(context, pool.platform_fee_recipient).await
+        + get_token_amount(context, pool.stack_fee_escrow).await
+}
+
+#[tokio::test]
+async fn reward_fee_is_worth_the_commission_at_the_post_reward_rate() {
+    let mut context = program_test().start_with_context().await;
+    let pool = setup_pool(&mut context).await;
+
+    let staker = add_staker(&mut context, &pool, STAKE_AMOUNT + LAMPORTS_PER_SOL).await;
+    process(
+        &mut context,
+        &[pool.stake(&staker.pubkey(), STAKE_AMOUNT)],
+        &[&staker],
+    )
+    .await
+    .unwrap();
+
+    // era 1 bonds the deposit, nothing is earned yet
+    warp_to_next_epoch(&mut context).await;
+    let stake_account = Keypair::new();
+    let instructions = [
+        pool.era_new(),
+        pool.era_bond(&context.payer.pubkey(), &stake_account.pubkey()),
+        pool.era_update_active(&stake_account.pubkey()),
+        pool.era_update_rate(),
+    ];
+    process(&mut context, &instructions, &[&stake_account])
+        .await
+        .unwrap();
+
+    let stake_manager: StakeManager = get_account(&mut context, pool.stake_manager).await;
+    assert!(stake_manager.era_process_data.is_empty());
+    assert_eq!(stake_manager.stake_accounts, vec![stake_account.pubkey()]);
+    assert_eq!(stake_manager.active, STAKE_AMOUNT);
+    assert_eq!(stake_manager.rate, StakeManager::DEFAULT_RATE);
+    assert_eq!(stake_manager.era_rates.len(), 1);
+    assert_eq!(fee_minted(&mut context, &pool).await, 0);
+
+    // era 2 earns a reward, the platform and stack fee minted for it are worth the
+    // commission share of the reward at the new rate
+    let reward = STAKE_AMOUNT / 2_500;
+    add_stake_reward(&mut context, stake_account.pubkey(), reward).await;
+    warp_to_next_epoch(&mut context).await;
+    let instructions = [
+        pool.era_new(),
+        pool.era_update_active(&stake_account.pubkey()),
+        pool.era_update_rate(),
+    ];
+    process(&mut context, &instructions, &[]).await.unwrap();
+
+    let stake_manager: StakeManager = get_account(&mut context, pool.stake_manager).await;
+    assert_eq!(stake_manager.active, STAKE_AMOUNT + reward);
+    assert!(stake_manager.rate > StakeManager::DEFAULT_RATE);
+    assert_eq!(stake_manager.era_rates.len(), 2);
+
+    let fee_value = fee_minted(&mut context, &pool).await as u128 * stake_manager.rate as u128
+        / StakeManager::DEFAULT_RATE as u128;
+    let expected_fee_value = reward as u128 * StakeManager::DEFAULT_PLATFORM_FEE_COMMISSION as u128
+        / StakeManager::DEFAULT_RATE as u128;
+    assert!(fee_value.abs_diff(expected_fee_value) <= 1);
+}
+
+#[tokio::test]
+async fn era_update_rate_waits_for_every_stake_account() {
+    let mut context = program_test().start_with_context().await;
+    let pool = setup_pool(&mut context).await;
+
+    let staker = add_staker(&mut context, &pool, STAKE_AMOUNT + LAMPORTS_PER_SOL).await;
+    process(
+        &mut context,
+        &[pool.stake(&staker.pubkey(), STAKE_AMOUNT)],
+        &[&staker],
+    )
+    .await
+    .unwrap();
+
+    warp_to_next_epoch(&mut context).await;
+    let stake_account = Keypair::new();
+    let instructions = [
+        pool.era_new(),
+        pool.era_bond(&context.payer.pubkey(), &stake_account.pubkey()),
+    ];
+    process(&mut context, &instructions, &[&stake_account])
+        .await
+        .unwrap();
+
+    assert_error(
+        process(&mut context, &[pool.era_update_rate()], &[]).await,
+        lsd_program::Errors::EraNoNeedUpdateRate,
+    );
+    assert_error(
+        process(&mut context, &[pool.era_new()], &[]).await,
+        lsd_program::Errors::EraIsLatest,
+    );
+
+    let stake_manager: StakeManager = get_account(&mut context, pool.stake_manager).await;
+    assert_eq!(
+        stake_manager.era_process_data.pending_stake_accounts,
+        vec![stake_account.pubkey()]
+    );
+    assert_eq!(
+        get_lamports(&mut context, pool.stake_pool).await,
+        stake_manager.rent_exempt_for_pool_acc
+    );
+}
+
+#[tokio::test]
+async fn repeated_catch_up_reaches_the_current_epoch() {
+    let mut context = program_test().start_with_context().await;
+    let pool = setup_pool(&mut context).await;
+
+    let stake_manager: StakeManager = get_account(&mut context, pool.stake_manager).await;
+    let start_era = stake_manager.latest_era;
+    let epoch = get_epoch(&mut context).await + 25;
+    context.warp_to_epoch(epoch).unwrap();
+    context.warp_forward_force_reward_interval_end().unwrap();
+
+    // each catch up covers at most MAX_CATCH_UP_EPOCHS, an outage of 25 epochs takes three
+    let mut latest_eras = vec![];
+    for _ in 0..3 {
+        context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+        process(&mut context, &[pool.era_new_catch_up()], &[])
+            .await
+            .unwrap();
+        let stake_manager: StakeManager = get_account(&mut context, pool.stake_manager).await;
+        latest_eras.push(stake_manager.latest_era);
+    }
+    assert_eq!(
+        latest_eras,
+        vec![
+            start_era + StakeManager::MAX_CATCH_UP_EPOCHS,
+            start_era + 2 * StakeManager::MAX_CATCH_UP_EPOCHS,
+            epoch,
+        ]
+    );
+
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    assert_error(
+        process(&mut context, &[pool.era_new_catch_up()], &[]).await,
+        lsd_program::Errors::EraIsLatest,
+    );
+}
@@ -0,0 +1,87 @@
+mod common;
+
+use anchor_spl::associated_token::get_associated_token_address;
+use common::*;
+use lsd_program::{accounts, Errors, Stack, StakeManager};
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, signature::Keypair, signer::Signer};
+
+const STAKE_AMOUNT: u64 = 10 * LAMPORTS_PER_SOL;
+
+#[tokio::test]
+async fn fees_go_to_the_configured_recipients() {
+    let mut context = program_test().start_with_context().await;
+    let pool = setup_pool(&mut context).await;
+    let payer = context.payer.pubkey();
+    let treasury = Keypair::new().pubkey();
+    let treasury_token_account = get_associated_token_address(&treasury, &pool.lsd_token_mint);
+
+    let staker = add_staker(&mut context, &pool, STAKE_AMOUNT + LAMPORTS_PER_SOL).await;
+    process(
+        &mut context,
+        &[pool.stake(&staker.pubkey(), STAKE_AMOUNT)],
+        &[&staker],
+    )
+    .await
+    .unwrap();
+    let stake_account = bond_era(&mut context, &pool).await;
+
+    let set_platform_fee_recipient = |admin| {
+        instruction(
+            accounts::SetPlatformFeeRecipient {
+                stake_manager: pool.stake_manager,
+                admin,
+            },
+            lsd_program::instruction::SetPlatformFeeRecipient {
+                platform_fee_recipient: treasury,
+            },
+        )
+    };
+    let set_stack_fee_recipient = instruction(
+        accounts::SetStackFeeRecipient {
+            stack: pool.stack,
+            admin: pool.admin.pubkey(),
+        },
+        lsd_program::instruction::SetStackFeeRecipient {
+            stack_fee_recipient: treasury,
+        },
+    );
+    assert_error(
+        process(&mut context, &[set_platform_fee_recipient(payer)], &[]).await,
+        Errors::AdminNotMatch,
+    );
+    let instructions = [
+        set_platform_fee_recipient(pool.admin.pubkey()),
+        set_stack_fee_recipient,
+        create_associated_token_account(payer, treasury, pool.lsd_token_mint),
+    ];
+    process(&mut context, &instructions, &[&pool.admin])
+        .await
+        .unwrap();
+
+    let stake_manager: StakeManager = get_account(&mut context, pool.stake_manager).await;
+    assert_eq!(stake_manager.platform_fee_recipient, treasury);
+    let stack: Stack = get_account(&mut context, pool.stack).await;
+    assert_eq!(stack.stack_fee_recipient, treasury);
+
+    // the platform fee of the next reward is minted to the treasury, not the admin
+    add_stake_reward(&mut context, stake_account, STAKE_AMOUNT / 2_500).await;
+    warp_to_next_epoch(&mut context).await;
+    let instructions = [
+        pool.era_new(),
+        pool.era_update_active(&stake_account),
+        instruction(
+            accounts::EraUpdateRate {
+                platform_fee_recipient: treasury_token_account,
+                ..pool.era_update_rate_accounts()
+            },
+            lsd_program::instruction::EraUpdateRate {},
+        ),
+    ];
+    process(&mut context, &instructions, &[]).await.unwrap();
+
+    assert!(get_token_amount(&mut context, treasury_token_account).await > 0);
+    assert_eq!(
+        get_token_amount(&mut context, pool.platform_fee_recipient).await,
+        0
+    );
+}
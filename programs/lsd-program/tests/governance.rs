@@ -0,0 +1,279 @@
+mod common;
+
+use anchor_lang::{
+    solana_program::instruction::{AccountMeta, Instruction},
+    InstructionData,
+};
+use common::*;
+use lsd_program::{accounts, instruction, Errors, Governance, Proposal, ProposalAccount};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    packet::PACKET_DATA_SIZE, pubkey::Pubkey, signature::Keypair, signer::Signer, system_program,
+};
+
+struct GovernanceFixture {
+    governance: Keypair,
+    governance_signer: Pubkey,
+    signers: Vec<Keypair>,
+}
+
+async fn create_governance(context: &mut ProgramTestContext, threshold: u8) -> GovernanceFixture {
+    let governance = Keypair::new();
+    let signers = vec![Keypair::new(), Keypair::new(), Keypair::new()];
+    let (governance_signer, _) = Pubkey::find_program_address(
+        &[&governance.pubkey().to_bytes(), Governance::SIGNER_SEED],
+        &lsd_program::ID,
+    );
+
+    process(
+        context,
+        &[common::instruction(
+            accounts::CreateGovernance {
+                governance: governance.pubkey(),
+                governance_signer,
+                rent_payer: context.payer.pubkey(),
+                system_program: system_program::ID,
+            },
+            instruction::CreateGovernance {
+                signers: signers.iter().map(|e| e.pubkey()).collect(),
+                threshold,
+            },
+        )],
+        &[&governance],
+    )
+    .await
+    .unwrap();
+
+    GovernanceFixture {
+        governance,
+        governance_signer,
+        signers,
+    }
+}
+
+// proposal that replaces the signer set of the governance itself
+fn set_signers_proposal(
+    fixture: &GovernanceFixture,
+    signers: Vec<Pubkey>,
+) -> instruction::CreateProposal {
+    instruction::CreateProposal {
+        accounts: vec![
+            ProposalAccount {
+                pubkey: fixture.governance.pubkey(),
+                is_signer: false,
+                is_writable: true,
+            },
+            ProposalAccount {
+                pubkey: fixture.governance_signer,
+                is_signer: true,
+                is_writable: false,
+            },
+        ],
+        data: InstructionData::data(&instruction::SetGovernanceSigners {
+            signers,
+            threshold: 1,
+        }),
+    }
+}
+
+fn create_proposal_instruction(
+    context: &ProgramTestContext,
+    fixture: &GovernanceFixture,
+    proposal: &Keypair,
+    data: instruction::CreateProposal,
+) -> Instruction {
+    common::instruction(
+        accounts::CreateProposal {
+            governance: fixture.governance.pubkey(),
+            proposal: proposal.pubkey(),
+            proposer: fixture.signers[0].pubkey(),
+            rent_payer: context.payer.pubkey(),
+            system_program: system_program::ID,
+        },
+        data,
+    )
+}
+
+#[tokio::test]
+async fn proposal_is_executed_through_self_cpi_and_closed() {
+    let mut context = program_test().start_with_context().await;
+    let fixture = create_governance(&mut context, 2).await;
+    let new_signer = Pubkey::new_unique();
+
+    let proposal = Keypair::new();
+    let create = create_proposal_instruction(
+        &context,
+        &fixture,
+        &proposal,
+        set_signers_proposal(&fixture, vec![new_signer]),
+    );
+    process(&mut context, &[create], &[&fixture.signers[0], &proposal])
+        .await
+        .unwrap();
+
+    let mut execute = common::instruction(
+        accounts::ExecuteProposal {
+            governance: fixture.governance.pubkey(),
+            governance_signer: fixture.governance_signer,
+            proposal: proposal.pubkey(),
+        },
+        instruction::ExecuteProposal {},
+    );
+    // remaining accounts: the proposed instruction's accounts and the program itself
+    execute.accounts.extend([
+        AccountMeta::new(fixture.governance.pubkey(), false),
+        AccountMeta::new_readonly(fixture.governance_signer, false),
+        AccountMeta::new_readonly(lsd_program::ID, false),
+    ]);
+
+    // one approval of two
+    assert_error(
+        process(&mut context, &[execute.clone()], &[]).await,
+        Errors::ProposalNotApproved,
+    );
+
+    let approve = common::instruction(
+        accounts::ApproveProposal {
+            governance: fixture.governance.pubkey(),
+            proposal: proposal.pubkey(),
+            signer: fixture.signers[1].pubkey(),
+        },
+        instruction::ApproveProposal {},
+    );
+    process(&mut context, &[approve], &[&fixture.signers[1]])
+        .await
+        .unwrap();
+
+    // not closable before execution
+    let close = common::instruction(
+        accounts::CloseProposal {
+            governance: fixture.governance.pubkey(),
+            proposal: proposal.pubkey(),
+            rent_payer: context.payer.pubkey(),
+        },
+        instruction::CloseProposal {},
+    );
+    assert_error(
+        process(&mut context, std::slice::from_ref(&close), &[]).await,
+        Errors::ProposalNotClosable,
+    );
+
+    // the failed execution above has the same signature
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    process(&mut context, &[execute], &[]).await.unwrap();
+
+    let governance: Governance = get_account(&mut context, fixture.governance.pubkey()).await;
+    assert_eq!(governance.signers, vec![new_signer]);
+    assert_eq!(governance.threshold, 1);
+    assert_eq!(governance.signers_seqno, 1);
+
+    let proposal_lamports = get_lamports(&mut context, proposal.pubkey()).await;
+    assert!(proposal_lamports > 0);
+    process(&mut context, &[close], &[]).await.unwrap();
+    assert_eq!(get_lamports(&mut context, proposal.pubkey()).await, 0);
+}
+
+#[tokio::test]
+async fn cancelled_proposal_cannot_execute_and_can_be_closed() {
+    let mut context = program_test().start_with_context().await;
+    let fixture = create_governance(&mut context, 1).await;
+
+    let proposal = Keypair::new();
+    let create = create_proposal_instruction(
+        &context,
+        &fixture,
+        &proposal,
+        set_signers_proposal(&fixture, vec![Pubkey::new_unique()]),
+    );
+    process(&mut context, &[create], &[&fixture.signers[0], &proposal])
+        .await
+        .unwrap();
+
+    let cancel = common::instruction(
+        accounts::CancelProposal {
+            governance: fixture.governance.pubkey(),
+            proposal: proposal.pubkey(),
+            signer: fixture.signers[2].pubkey(),
+        },
+        instruction::CancelProposal {},
+    );
+    process(&mut context, &[cancel], &[&fixture.signers[2]])
+        .await
+        .unwrap();
+
+    let execute = common::instruction(
+        accounts::ExecuteProposal {
+            governance: fixture.governance.pubkey(),
+            governance_signer: fixture.governance_signer,
+            proposal: proposal.pubkey(),
+        },
+        instruction::ExecuteProposal {},
+    );
+    assert_error(
+        process(&mut context, &[execute], &[]).await,
+        Errors::ProposalAlreadyCancelled,
+    );
+
+    let payer = context.payer.pubkey();
+    let payer_lamports = get_lamports(&mut context, payer).await;
+    let proposal_lamports = get_lamports(&mut context, proposal.pubkey()).await;
+    let close = common::instruction(
+        accounts::CloseProposal {
+            governance: fixture.governance.pubkey(),
+            proposal: proposal.pubkey(),
+            rent_payer: payer,
+        },
+        instruction::CloseProposal {},
+    );
+    process(&mut context, &[close], &[]).await.unwrap();
+
+    // rent back to the payer, less the transaction fee
+    let fee = 5000;
+    assert_eq!(
+        get_lamports(&mut context, payer).await,
+        payer_lamports + proposal_lamports - fee
+    );
+}
+
+#[tokio::test]
+async fn max_proposal_fits_in_one_transaction() {
+    let mut context = program_test().start_with_context().await;
+    let fixture = create_governance(&mut context, 1).await;
+
+    let proposal = Keypair::new();
+    let max = instruction::CreateProposal {
+        accounts: (0..Proposal::MAX_ACCOUNTS_LEN)
+            .map(|_| ProposalAccount {
+                pubkey: Pubkey::new_unique(),
+                is_signer: false,
+                is_writable: false,
+            })
+            .collect(),
+        data: vec![0; Proposal::MAX_DATA_LEN],
+    };
+    let create = create_proposal_instruction(&context, &fixture, &proposal, max);
+    let transaction = transaction(
+        &context,
+        std::slice::from_ref(&create),
+        &[&fixture.signers[0], &proposal],
+    );
+    assert!(transaction_size(&transaction) <= PACKET_DATA_SIZE);
+    process(&mut context, &[create], &[&fixture.signers[0], &proposal])
+        .await
+        .unwrap();
+
+    let too_large = Keypair::new();
+    let create = create_proposal_instruction(
+        &context,
+        &fixture,
+        &too_large,
+        instruction::CreateProposal {
+            accounts: vec![],
+            data: vec![0; Proposal::MAX_DATA_LEN + 1],
+        },
+    );
+    assert_error(
+        process(&mut context, &[create], &[&fixture.signers[0], &too_large]).await,
+        Errors::ProposalTooLarge,
+    );
+}
@@ -0,0 +1,286 @@
+mod common;
+
+use anchor_lang::solana_program::instruction::AccountMeta;
+use anchor_lang::{AnchorSerialize, Discriminator};
+use common::*;
+use lsd_program::{
+    accounts, instruction, EraProcessData, EraRateV0, Errors, StakeManager, StakeManagerV0,
+    UnstakeAccount, UnstakeAccountV0,
+};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    account::Account, pubkey::Pubkey, rent::Rent, signature::Keypair, signer::Signer,
+    system_program, sysvar,
+};
+
+const OLD_SIZE: usize = 2000;
+const NEW_SIZE: u32 = 4000;
+const POOL_BALANCE: u64 = 5_000_000_000;
+
+struct OldStakeManager {
+    stake_manager: Pubkey,
+    stake_pool: Pubkey,
+    admin: Keypair,
+    unstake_accounts: Vec<Pubkey>,
+}
+
+// stake manager and unstake accounts as written by the program before the appended fields
+async fn start_with_old_stake_manager(
+    era_rates: Vec<EraRateV0>,
+    unstake_amounts: &[u64],
+) -> (ProgramTestContext, OldStakeManager) {
+    let mut program_test = program_test();
+    let rent = Rent::default();
+
+    let stake_manager = Pubkey::new_unique();
+    let admin = Keypair::new();
+    let (stake_pool, pool_seed_bump) = Pubkey::find_program_address(
+        &[&stake_manager.to_bytes(), StakeManager::POOL_SEED],
+        &lsd_program::ID,
+    );
+    let rent_exempt_for_pool_acc = rent.minimum_balance(0);
+
+    let old = StakeManagerV0 {
+        admin: admin.pubkey(),
+        balancer: admin.pubkey(),
+        stack: Pubkey::new_unique(),
+        lsd_token_mint: Pubkey::new_unique(),
+        pool_seed_bump,
+        rent_exempt_for_pool_acc,
+        min_stake_amount: StakeManager::DEFAULT_MIN_STAKE_AMOUNT,
+        platform_fee_commission: StakeManager::DEFAULT_PLATFORM_FEE_COMMISSION,
+        stack_fee_commission: 0,
+        rate_change_limit: StakeManager::DEFAULT_RATE_CHANGE_LIMIT,
+        stake_accounts_len_limit: StakeManager::DEFAULT_STAKE_ACCOUNT_LEN_LIMIT,
+        split_accounts_len_limit: StakeManager::DEFAULT_SPLIT_ACCOUNT_LEN_LIMIT,
+        unbonding_duration: StakeManager::DEFAULT_UNBONDING_DURATION,
+        latest_era: 3,
+        rate: 1_100_000_000,
+        era_bond: 0,
+        era_unbond: 0,
+        active: 4_000_000_000,
+        total_platform_fee: 0,
+        validators: vec![Pubkey::new_unique()],
+        stake_accounts: vec![],
+        split_accounts: vec![],
+        era_rates,
+        era_process_data: EraProcessData::default(),
+    };
+    let mut data = StakeManager::DISCRIMINATOR.to_vec();
+    old.serialize(&mut data).unwrap();
+    data.resize(OLD_SIZE, 0);
+
+    program_test.add_account(
+        stake_manager,
+        Account {
+            lamports: rent.minimum_balance(OLD_SIZE),
+            data,
+            owner: lsd_program::ID,
+            ..Account::default()
+        },
+    );
+    program_test.add_account(
+        stake_pool,
+        Account {
+            lamports: rent_exempt_for_pool_acc + POOL_BALANCE,
+            owner: system_program::ID,
+            ..Account::default()
+        },
+    );
+
+    let unstake_accounts = unstake_amounts
+        .iter()
+        .map(|amount| {
+            let unstake_account = Pubkey::new_unique();
+            let mut data = UnstakeAccount::DISCRIMINATOR.to_vec();
+            UnstakeAccountV0 {
+                stake_manager,
+                recipient: Pubkey::new_unique(),
+                amount: *amount,
+                created_epoch: 0,
+            }
+            .serialize(&mut data)
+            .unwrap();
+            program_test.add_account(
+                unstake_account,
+                Account {
+                    lamports: rent.minimum_balance(data.len()),
+                    data,
+                    owner: lsd_program::ID,
+                    ..Account::default()
+                },
+            );
+            unstake_account
+        })
+        .collect();
+
+    let context = program_test.start_with_context().await;
+    (
+        context,
+        OldStakeManager {
+            stake_manager,
+            stake_pool,
+            admin,
+            unstake_accounts,
+        },
+    )
+}
+
+fn migrate_instruction(
+    context: &ProgramTestContext,
+    old: &OldStakeManager,
+) -> anchor_lang::solana_program::instruction::Instruction {
+    common::instruction(
+        accounts::MigrateStakeManager {
+            stake_manager: old.stake_manager,
+            stake_pool: old.stake_pool,
+            admin: old.admin.pubkey(),
+            rent_payer: context.payer.pubkey(),
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+        },
+        instruction::MigrateStakeManager { new_size: NEW_SIZE },
+    )
+}
+
+#[tokio::test]
+async fn migrate_converts_old_layout_and_seeds_tracked_pool_balance() {
+    let (mut context, old) = start_with_old_stake_manager(
+        vec![
+            EraRateV0 {
+                era: 1,
+                rate: 1_050_000_000,
+            },
+            EraRateV0 {
+                era: 2,
+                rate: 1_100_000_000,
+            },
+        ],
+        &[],
+    )
+    .await;
+
+    let migrate = migrate_instruction(&context, &old);
+    process(&mut context, std::slice::from_ref(&migrate), &[&old.admin])
+        .await
+        .unwrap();
+
+    let stake_manager: StakeManager = get_account(&mut context, old.stake_manager).await;
+    assert_eq!(stake_manager.version, StakeManager::CURRENT_VERSION);
+    assert_eq!(stake_manager.active, 4_000_000_000);
+    assert_eq!(stake_manager.rate, 1_100_000_000);
+    assert_eq!(stake_manager.tracked_pool_balance, POOL_BALANCE);
+    assert_eq!(stake_manager.platform_fee_recipient, old.admin.pubkey());
+    assert_eq!(stake_manager.fee_manager, old.admin.pubkey());
+    assert_eq!(stake_manager.era_rates.len(), 2);
+    assert_eq!(stake_manager.era_rates[1].rate, 1_100_000_000);
+    assert_eq!(stake_manager.era_rates[1].era_epochs, 1);
+
+    let account = context
+        .banks_client
+        .get_account(old.stake_manager)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(account.data.len(), NEW_SIZE as usize);
+    assert!(account.lamports >= Rent::default().minimum_balance(NEW_SIZE as usize));
+
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    assert_error(
+        process(&mut context, &[migrate], &[&old.admin]).await,
+        Errors::StakeManagerAlreadyMigrated,
+    );
+}
+
+#[tokio::test]
+async fn old_stake_manager_cannot_start_an_era_before_migration() {
+    let (mut context, old) = start_with_old_stake_manager(vec![], &[]).await;
+
+    let era_new = common::instruction(
+        accounts::EraNew {
+            stake_manager: old.stake_manager,
+            stake_pool: old.stake_pool,
+            clock: sysvar::clock::ID,
+            cranker: None,
+            cranker_account: None,
+        },
+        instruction::EraNew {},
+    );
+    assert_error(
+        process(&mut context, &[era_new], &[]).await,
+        Errors::StakeManagerNotMigrated,
+    );
+}
+
+#[tokio::test]
+async fn migrate_queues_old_unstake_accounts() {
+    let (mut context, old) =
+        start_with_old_stake_manager(vec![], &[1_000_000_000, 2_000_000_000]).await;
+
+    // the first unstake account is converted with the stake manager
+    let mut migrate = migrate_instruction(&context, &old);
+    migrate
+        .accounts
+        .push(AccountMeta::new(old.unstake_accounts[0], false));
+    process(&mut context, &[migrate], &[&old.admin])
+        .await
+        .unwrap();
+
+    // the second one is left out and converted on its own
+    let migrate_unstake_account = common::instruction(
+        accounts::MigrateUnstakeAccount {
+            stake_manager: old.stake_manager,
+            unstake_account: old.unstake_accounts[1],
+            rent_payer: context.payer.pubkey(),
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+        },
+        instruction::MigrateUnstakeAccount {},
+    );
+    process(
+        &mut context,
+        std::slice::from_ref(&migrate_unstake_account),
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let stake_manager: StakeManager = get_account(&mut context, old.stake_manager).await;
+    assert_eq!(stake_manager.unstake_requested, 3_000_000_000);
+    assert_eq!(stake_manager.total_unstake_pending, 3_000_000_000);
+
+    let first: UnstakeAccount = get_account(&mut context, old.unstake_accounts[0]).await;
+    let second: UnstakeAccount = get_account(&mut context, old.unstake_accounts[1]).await;
+    assert_eq!(first.queue_position, 0);
+    assert_eq!(first.era, 1);
+    assert_eq!(first.rent_payer, first.recipient);
+    assert_eq!(second.queue_position, 1_000_000_000);
+    assert_eq!(second.amount, 2_000_000_000);
+
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    assert_error(
+        process(&mut context, &[migrate_unstake_account], &[]).await,
+        Errors::UnstakeAccountAlreadyMigrated,
+    );
+
+    // converted unstake accounts are withdrawn like new ones
+    context
+        .warp_to_epoch(StakeManager::DEFAULT_UNBONDING_DURATION)
+        .unwrap();
+    let withdraw = common::instruction(
+        accounts::Withdraw {
+            stake_manager: old.stake_manager,
+            stake_pool: old.stake_pool,
+            unstake_account: old.unstake_accounts[0],
+            recipient: first.recipient,
+            rent_payer: first.rent_payer,
+            clock: sysvar::clock::ID,
+            system_program: system_program::ID,
+        },
+        instruction::Withdraw {},
+    );
+    process(&mut context, &[withdraw], &[]).await.unwrap();
+    assert!(get_lamports(&mut context, first.recipient).await > 1_000_000_000);
+    let stake_manager: StakeManager = get_account(&mut context, old.stake_manager).await;
+    assert_eq!(stake_manager.total_unstake_pending, 2_000_000_000);
+}
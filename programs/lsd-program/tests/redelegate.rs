@@ -0,0 +1,112 @@
+mod common;
+
+use common::*;
+use lsd_program::{accounts, StakeManager};
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    native_token::LAMPORTS_PER_SOL,
+    pubkey::Pubkey,
+    signature::Keypair,
+    signer::Signer,
+    stake::{self, state::StakeStateV2},
+    system_program,
+    sysvar::{self, rent::Rent},
+};
+
+const STAKE_AMOUNT: u64 = 10 * LAMPORTS_PER_SOL;
+
+async fn add_validator(context: &mut ProgramTestContext, pool: &Pool) -> Pubkey {
+    let validator = add_vote_account(context).await;
+    process(
+        context,
+        &[instruction(
+            accounts::AddValidator {
+                stake_manager: pool.stake_manager,
+                authority: pool.admin.pubkey(),
+            },
+            lsd_program::instruction::AddValidator {
+                new_validator: validator,
+            },
+        )],
+        &[&pool.admin],
+    )
+    .await
+    .unwrap();
+    validator
+}
+
+#[tokio::test]
+async fn redelegate_source_rent_is_refunded_on_withdraw() {
+    let mut context = program_test().start_with_context().await;
+    let pool = setup_pool(&mut context).await;
+    let payer = context.payer.pubkey();
+    let stake_rent = Rent::default().minimum_balance(StakeStateV2::size_of());
+
+    let staker = add_staker(&mut context, &pool, STAKE_AMOUNT + LAMPORTS_PER_SOL).await;
+    process(
+        &mut context,
+        &[pool.stake(&staker.pubkey(), STAKE_AMOUNT)],
+        &[&staker],
+    )
+    .await
+    .unwrap();
+    let stake_account = bond_era(&mut context, &pool).await;
+    let to_validator = add_validator(&mut context, &pool).await;
+    warp_to_next_epoch(&mut context).await;
+
+    // the whole stake moves, the bonded account becomes a split account holding its rent
+    let (split_stake_account, to_stake_account) = (Keypair::new(), Keypair::new());
+    #[allow(deprecated)]
+    let redelegate = instruction(
+        accounts::Redelegate {
+            stake_manager: pool.stake_manager,
+            balancer: pool.admin.pubkey(),
+            to_validator,
+            stake_pool: pool.stake_pool,
+            from_stake_account: stake_account,
+            split_stake_account: split_stake_account.pubkey(),
+            to_stake_account: to_stake_account.pubkey(),
+            rent_payer: payer,
+            clock: sysvar::clock::ID,
+            stake_config: stake::config::ID,
+            stake_history: sysvar::stake_history::ID,
+            stake_program: stake::program::ID,
+            system_program: system_program::ID,
+        },
+        lsd_program::instruction::Redelegate {
+            redelegate_amount: STAKE_AMOUNT,
+        },
+    );
+    process(
+        &mut context,
+        &[redelegate],
+        &[&pool.admin, &split_stake_account, &to_stake_account],
+    )
+    .await
+    .unwrap();
+    let stake_manager: StakeManager = get_account(&mut context, pool.stake_manager).await;
+    assert!(stake_manager.split_accounts.contains(&stake_account));
+    assert_eq!(get_lamports(&mut context, stake_account).await, stake_rent);
+    let tracked_pool_balance = stake_manager.tracked_pool_balance;
+
+    warp_to_next_epoch(&mut context).await;
+    let payer_before = get_lamports(&mut context, payer).await;
+    process(
+        &mut context,
+        &[pool.era_withdraw(&stake_account, Some(payer))],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        get_lamports(&mut context, payer).await,
+        payer_before - 5000 + stake_rent
+    );
+    let stake_manager: StakeManager = get_account(&mut context, pool.stake_manager).await;
+    assert!(!stake_manager
+        .rent_payers
+        .iter()
+        .any(|e| e.stake_account == stake_account));
+    assert_eq!(stake_manager.tracked_pool_balance, tracked_pool_balance);
+}
@@ -0,0 +1,146 @@
+mod common;
+
+use common::*;
+use lsd_program::{accounts, instruction, Errors, Role, StakeManager};
+use solana_sdk::{
+    instruction::Instruction, native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, signature::Keypair,
+    signer::Signer, sysvar,
+};
+
+fn set_role(pool: &Pool, admin: Pubkey, role: Role, new_key: Pubkey) -> Instruction {
+    common::instruction(
+        accounts::SetRole {
+            stake_manager: pool.stake_manager,
+            admin,
+        },
+        instruction::SetRole { role, new_key },
+    )
+}
+
+fn set_paused(pool: &Pool, pauser: Pubkey, paused: bool) -> Instruction {
+    common::instruction(
+        accounts::SetPaused {
+            stake_manager: pool.stake_manager,
+            pauser,
+        },
+        instruction::SetPaused { paused },
+    )
+}
+
+fn set_deposit_fee_bps(pool: &Pool, authority: Pubkey, deposit_fee_bps: u64) -> Instruction {
+    common::instruction(
+        accounts::SetDepositFeeBps {
+            stake_manager: pool.stake_manager,
+            stack: pool.stack,
+            authority,
+            clock: sysvar::clock::ID,
+        },
+        instruction::SetDepositFeeBps { deposit_fee_bps },
+    )
+}
+
+#[tokio::test]
+async fn roles_are_assigned_by_the_admin_and_limited_to_their_instructions() {
+    let mut context = program_test().start_with_context().await;
+    let pool = setup_pool(&mut context).await;
+    let operator = Keypair::new();
+
+    assert_error(
+        process(
+            &mut context,
+            &[set_role(
+                &pool,
+                operator.pubkey(),
+                Role::Pauser,
+                operator.pubkey(),
+            )],
+            &[&operator],
+        )
+        .await,
+        Errors::AdminNotMatch,
+    );
+    assert_error(
+        process(
+            &mut context,
+            &[set_paused(&pool, operator.pubkey(), true)],
+            &[&operator],
+        )
+        .await,
+        Errors::RoleNotMatch,
+    );
+
+    process(
+        &mut context,
+        &[set_role(
+            &pool,
+            pool.admin.pubkey(),
+            Role::Pauser,
+            operator.pubkey(),
+        )],
+        &[&pool.admin],
+    )
+    .await
+    .unwrap();
+    let stake_manager: StakeManager = get_account(&mut context, pool.stake_manager).await;
+    assert_eq!(stake_manager.role(Role::Pauser), operator.pubkey());
+
+    // the pauser pauses deposits but can't touch the fees, the same transaction failed above
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    process(
+        &mut context,
+        &[set_paused(&pool, operator.pubkey(), true)],
+        &[&operator],
+    )
+    .await
+    .unwrap();
+    let staker = add_staker(&mut context, &pool, 2 * LAMPORTS_PER_SOL).await;
+    assert_error(
+        process(
+            &mut context,
+            &[pool.stake(&staker.pubkey(), LAMPORTS_PER_SOL)],
+            &[&staker],
+        )
+        .await,
+        Errors::StakeManagerPaused,
+    );
+    assert_error(
+        process(
+            &mut context,
+            &[set_deposit_fee_bps(&pool, operator.pubkey(), 10)],
+            &[&operator],
+        )
+        .await,
+        Errors::RoleNotMatch,
+    );
+
+    process(
+        &mut context,
+        &[
+            set_role(
+                &pool,
+                pool.admin.pubkey(),
+                Role::FeeManager,
+                operator.pubkey(),
+            ),
+            set_paused(&pool, operator.pubkey(), false),
+        ],
+        &[&pool.admin, &operator],
+    )
+    .await
+    .unwrap();
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    process(
+        &mut context,
+        &[set_deposit_fee_bps(&pool, operator.pubkey(), 10)],
+        &[&operator],
+    )
+    .await
+    .unwrap();
+    process(
+        &mut context,
+        &[pool.stake(&staker.pubkey(), LAMPORTS_PER_SOL)],
+        &[&staker],
+    )
+    .await
+    .unwrap();
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use anchor_spl::associated_token::get_associated_token_address;
+use common::*;
+use lsd_program::{accounts, Errors};
+use solana_sdk::{
+    instruction::Instruction, native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, signature::Keypair,
+    signer::Signer,
+};
+
+const STAKE_AMOUNT: u64 = 10 * LAMPORTS_PER_SOL;
+
+fn claim_stack_fee(pool: &Pool, admin: Pubkey, claim_to: Pubkey, claim_amount: u64) -> Instruction {
+    instruction(
+        accounts::ClaimStackFee {
+            stack: pool.stack,
+            admin,
+            lsd_token_mint: pool.lsd_token_mint,
+            stack_fee_account: pool.stack_fee_account,
+            stack_fee_escrow: pool.stack_fee_escrow,
+            claim_to,
+            token_program: anchor_spl::token::ID,
+        },
+        lsd_program::instruction::ClaimStackFee { claim_amount },
+    )
+}
+
+#[tokio::test]
+async fn stack_admin_claims_escrowed_fee_to_any_token_account() {
+    let mut context = program_test().start_with_context().await;
+    let pool = setup_pool(&mut context).await;
+    let payer = context.payer.pubkey();
+
+    let staker = add_staker(&mut context, &pool, STAKE_AMOUNT + LAMPORTS_PER_SOL).await;
+    process(
+        &mut context,
+        &[pool.stake(&staker.pubkey(), STAKE_AMOUNT)],
+        &[&staker],
+    )
+    .await
+    .unwrap();
+    let stake_account = bond_era(&mut context, &pool).await;
+
+    add_stake_reward(&mut context, stake_account, STAKE_AMOUNT / 2_500).await;
+    warp_to_next_epoch(&mut context).await;
+    let instructions = [
+        pool.era_new(),
+        pool.era_update_active(&stake_account),
+        pool.era_update_rate(),
+    ];
+    process(&mut context, &instructions, &[]).await.unwrap();
+    let escrowed = get_token_amount(&mut context, pool.stack_fee_escrow).await;
+    assert!(escrowed > 0);
+
+    // the destination is owned by neither the admin nor the stack fee recipient
+    let owner = Keypair::new().pubkey();
+    let claim_to = get_associated_token_address(&owner, &pool.lsd_token_mint);
+    process(
+        &mut context,
+        &[create_associated_token_account(
+            payer,
+            owner,
+            pool.lsd_token_mint,
+        )],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    assert_error(
+        process(
+            &mut context,
+            &[claim_stack_fee(&pool, payer, claim_to, escrowed)],
+            &[],
+        )
+        .await,
+        Errors::AdminNotMatch,
+    );
+    assert_error(
+        process(
+            &mut context,
+            &[claim_stack_fee(
+                &pool,
+                pool.admin.pubkey(),
+                claim_to,
+                escrowed + 1,
+            )],
+            &[&pool.admin],
+        )
+        .await,
+        Errors::BalanceNotEnough,
+    );
+    process(
+        &mut context,
+        &[claim_stack_fee(
+            &pool,
+            pool.admin.pubkey(),
+            claim_to,
+            escrowed,
+        )],
+        &[&pool.admin],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(get_token_amount(&mut context, claim_to).await, escrowed);
+    assert_eq!(
+        get_token_amount(&mut context, pool.stack_fee_escrow).await,
+        0
+    );
+}
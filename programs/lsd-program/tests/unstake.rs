@@ -0,0 +1,155 @@
+mod common;
+
+use common::*;
+use lsd_program::{Errors, StakeManager, UnstakeAccount, UnstakeNonceAccount};
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, signature::Keypair, signer::Signer};
+
+const STAKE_AMOUNT: u64 = 10 * LAMPORTS_PER_SOL;
+
+#[tokio::test]
+async fn redirected_unstake_is_keyed_on_the_owner() {
+    let mut context = program_test().start_with_context().await;
+    let pool = setup_pool(&mut context).await;
+
+    let owner = add_staker(&mut context, &pool, STAKE_AMOUNT + LAMPORTS_PER_SOL).await;
+    let other = add_staker(&mut context, &pool, STAKE_AMOUNT + LAMPORTS_PER_SOL).await;
+    for staker in [&owner, &other] {
+        process(
+            &mut context,
+            &[pool.stake(&staker.pubkey(), STAKE_AMOUNT)],
+            &[staker],
+        )
+        .await
+        .unwrap();
+    }
+
+    // unstakes redirected to the owner take other's nonce, not the owner's
+    let redirected = pool.unstake_account(&other.pubkey(), 0);
+    process(
+        &mut context,
+        &[pool.unstake_to(
+            &other.pubkey(),
+            0,
+            LAMPORTS_PER_SOL,
+            Some(owner.pubkey()),
+            &other.pubkey(),
+        )],
+        &[&other],
+    )
+    .await
+    .unwrap();
+    let ticket: UnstakeAccount = get_account(&mut context, redirected).await;
+    assert_eq!(ticket.recipient, owner.pubkey());
+    assert_eq!(ticket.rent_payer, other.pubkey());
+    assert!(!account_exists(&mut context, pool.unstake_nonce_account(&owner.pubkey())).await);
+
+    process(
+        &mut context,
+        &[pool.unstake(&owner.pubkey(), 0, LAMPORTS_PER_SOL)],
+        &[&owner],
+    )
+    .await
+    .unwrap();
+
+    bond_era(&mut context, &pool).await;
+    for _ in 1..StakeManager::DEFAULT_UNBONDING_DURATION {
+        warp_to_next_epoch(&mut context).await;
+    }
+
+    let owner_before = get_lamports(&mut context, owner.pubkey()).await;
+    let other_before = get_lamports(&mut context, other.pubkey()).await;
+    let ticket_rent = get_lamports(&mut context, redirected).await;
+    process(
+        &mut context,
+        &[pool.withdraw_refund_to(redirected, owner.pubkey(), other.pubkey())],
+        &[],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        get_lamports(&mut context, owner.pubkey()).await,
+        owner_before + LAMPORTS_PER_SOL
+    );
+    assert_eq!(
+        get_lamports(&mut context, other.pubkey()).await,
+        other_before + ticket_rent
+    );
+}
+
+#[tokio::test]
+async fn transferred_ticket_is_keyed_on_the_sender() {
+    let mut context = program_test().start_with_context().await;
+    let pool = setup_pool(&mut context).await;
+    let new_recipient = Keypair::new().pubkey();
+
+    let staker = add_staker(&mut context, &pool, STAKE_AMOUNT + LAMPORTS_PER_SOL).await;
+    let ticket = pool.unstake_account(&staker.pubkey(), 0);
+    let instructions = [
+        pool.stake(&staker.pubkey(), STAKE_AMOUNT),
+        pool.unstake(&staker.pubkey(), 0, LAMPORTS_PER_SOL),
+    ];
+    process(&mut context, &instructions, &[&staker])
+        .await
+        .unwrap();
+
+    let transferred = pool.unstake_account(&staker.pubkey(), 1);
+    process(
+        &mut context,
+        &[pool.transfer_unstake_ticket(
+            ticket,
+            &staker.pubkey(),
+            staker.pubkey(),
+            1,
+            new_recipient,
+        )],
+        &[&staker],
+    )
+    .await
+    .unwrap();
+
+    assert!(!account_exists(&mut context, ticket).await);
+    let ticket: UnstakeAccount = get_account(&mut context, transferred).await;
+    assert_eq!(ticket.recipient, new_recipient);
+    assert_eq!(ticket.amount, LAMPORTS_PER_SOL);
+    let nonce_account: UnstakeNonceAccount =
+        get_account(&mut context, pool.unstake_nonce_account(&staker.pubkey())).await;
+    assert_eq!(nonce_account.nonce, 2);
+    assert!(!account_exists(&mut context, pool.unstake_nonce_account(&new_recipient)).await);
+}
+
+#[tokio::test]
+async fn unstake_worth_no_lamports_is_rejected() {
+    let mut context = program_test().start_with_context().await;
+    let pool = setup_pool(&mut context).await;
+
+    let staker = add_staker(&mut context, &pool, STAKE_AMOUNT + LAMPORTS_PER_SOL).await;
+    process(
+        &mut context,
+        &[pool.stake(&staker.pubkey(), STAKE_AMOUNT)],
+        &[&staker],
+    )
+    .await
+    .unwrap();
+
+    // after a loss one lsd token lamport is worth less than a lamport
+    let mut stake_manager: StakeManager = get_account(&mut context, pool.stake_manager).await;
+    stake_manager.rate = StakeManager::CAL_BASE / 2;
+    set_account(&mut context, pool.stake_manager, &stake_manager).await;
+
+    assert_error(
+        process(
+            &mut context,
+            &[pool.unstake(&staker.pubkey(), 0, 1)],
+            &[&staker],
+        )
+        .await,
+        Errors::UnstakeAmountIsZero,
+    );
+    process(
+        &mut context,
+        &[pool.unstake(&staker.pubkey(), 0, 2)],
+        &[&staker],
+    )
+    .await
+    .unwrap();
+}
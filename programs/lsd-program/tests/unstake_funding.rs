@@ -0,0 +1,102 @@
+mod common;
+
+use common::*;
+use lsd_program::{accounts, instruction, Errors, StakeManager, UnstakeAccount};
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, signature::Keypair, signer::Signer, sysvar};
+
+const STAKE_AMOUNT: u64 = 10 * LAMPORTS_PER_SOL;
+
+#[tokio::test]
+async fn unstake_is_claimable_once_its_funding_split_is_withdrawn() {
+    let mut context = program_test().start_with_context().await;
+    let pool = setup_pool(&mut context).await;
+    let payer = context.payer.pubkey();
+
+    let staker = add_staker(&mut context, &pool, STAKE_AMOUNT + LAMPORTS_PER_SOL).await;
+    process(
+        &mut context,
+        &[pool.stake(&staker.pubkey(), STAKE_AMOUNT)],
+        &[&staker],
+    )
+    .await
+    .unwrap();
+    let stake_account = bond_era(&mut context, &pool).await;
+
+    let unstake_amount = 4 * LAMPORTS_PER_SOL;
+    let ticket = pool.unstake_account(&staker.pubkey(), 0);
+    process(
+        &mut context,
+        &[pool.unstake(&staker.pubkey(), 0, unstake_amount)],
+        &[&staker],
+    )
+    .await
+    .unwrap();
+    let created_epoch = get_epoch(&mut context).await;
+    let stake_manager: StakeManager = get_account(&mut context, pool.stake_manager).await;
+    let unstake_account: UnstakeAccount = get_account(&mut context, ticket).await;
+    assert_eq!(unstake_account.era, stake_manager.latest_era + 1);
+
+    let get_unstake_claim_epoch = || {
+        common::instruction(
+            accounts::GetUnstakeClaimEpoch {
+                stake_manager: pool.stake_manager,
+                unstake_account: ticket,
+                clock: sysvar::clock::ID,
+            },
+            instruction::GetUnstakeClaimEpoch {},
+        )
+    };
+
+    // the funding era splits the unstake off and deactivates it
+    warp_to_next_epoch(&mut context).await;
+    let split_account = Keypair::new();
+    let instructions = [
+        pool.era_new(),
+        pool.era_unbond(&payer, &stake_account, &split_account.pubkey()),
+        pool.era_update_active(&stake_account),
+        pool.era_update_rate(),
+    ];
+    process(&mut context, &instructions, &[&split_account])
+        .await
+        .unwrap();
+    let deactivation_epoch = get_epoch(&mut context).await;
+    let claim_epoch: u64 = view(&mut context, get_unstake_claim_epoch()).await;
+    assert_eq!(claim_epoch, deactivation_epoch + 1);
+    assert_eq!(
+        claim_epoch,
+        created_epoch + StakeManager::DEFAULT_UNBONDING_DURATION
+    );
+
+    // the minimum delay has passed but the lamports are still in the split account
+    warp_to_next_epoch(&mut context).await;
+    assert_error(
+        process(&mut context, &[pool.withdraw(ticket, staker.pubkey())], &[]).await,
+        Errors::UnstakeAccountNotClaimable,
+    );
+
+    let payer_before = get_lamports(&mut context, payer).await;
+    let split_rent = get_lamports(&mut context, split_account.pubkey()).await - unstake_amount;
+    process(
+        &mut context,
+        &[pool.era_withdraw(&split_account.pubkey(), Some(payer))],
+        &[],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        get_lamports(&mut context, payer).await,
+        payer_before - 5000 + split_rent
+    );
+
+    let staker_before = get_lamports(&mut context, staker.pubkey()).await;
+    let ticket_rent = get_lamports(&mut context, ticket).await;
+    context.last_blockhash = context.get_new_latest_blockhash().await.unwrap();
+    process(&mut context, &[pool.withdraw(ticket, staker.pubkey())], &[])
+        .await
+        .unwrap();
+    assert_eq!(
+        get_lamports(&mut context, staker.pubkey()).await,
+        staker_before + unstake_amount + ticket_rent
+    );
+    assert!(!account_exists(&mut context, ticket).await);
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use common::*;
+use lsd_program::StakeManager;
+use solana_program_test::ProgramTestContext;
+use solana_sdk::{
+    native_token::LAMPORTS_PER_SOL, pubkey::Pubkey, signature::Keypair, signer::Signer,
+};
+
+const STAKE_AMOUNT: u64 = 10 * LAMPORTS_PER_SOL;
+
+// next epoch's era bonding into a new stake account beside the bonded ones
+async fn run_era(context: &mut ProgramTestContext, pool: &Pool, bonded: &mut Vec<Pubkey>) {
+    warp_to_next_epoch(context).await;
+    let stake_account = Keypair::new();
+    let mut instructions = vec![
+        pool.era_new(),
+        pool.era_bond(&context.payer.pubkey(), &stake_account.pubkey()),
+    ];
+    bonded.push(stake_account.pubkey());
+    instructions.extend(bonded.iter().map(|e| pool.era_update_active(e)));
+    instructions.push(pool.era_update_rate());
+    process(context, &instructions, &[&stake_account])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn lamports_owed_to_unstakers_are_not_bonded() {
+    let mut context = program_test().start_with_context().await;
+    let pool = setup_pool(&mut context).await;
+    let rent = get_lamports(&mut context, pool.stake_pool).await;
+
+    let first = add_staker(&mut context, &pool, STAKE_AMOUNT + LAMPORTS_PER_SOL).await;
+    let second = add_staker(&mut context, &pool, STAKE_AMOUNT + LAMPORTS_PER_SOL).await;
+    process(
+        &mut context,
+        &[pool.stake(&first.pubkey(), STAKE_AMOUNT)],
+        &[&first],
+    )
+    .await
+    .unwrap();
+    let mut bonded = vec![bond_era(&mut context, &pool).await];
+
+    // the unstake is served from the deposit of the same era, only the rest is bonded
+    let instructions = [
+        pool.stake(&second.pubkey(), 3 * LAMPORTS_PER_SOL),
+        pool.unstake(&first.pubkey(), 0, 2 * LAMPORTS_PER_SOL),
+    ];
+    process(&mut context, &instructions, &[&first, &second])
+        .await
+        .unwrap();
+    let stake_manager: StakeManager = get_account(&mut context, pool.stake_manager).await;
+    assert_eq!(stake_manager.total_unstake_pending, 2 * LAMPORTS_PER_SOL);
+    assert_eq!(stake_manager.total_unstake_claimable, 0);
+
+    run_era(&mut context, &pool, &mut bonded).await;
+    let stake_manager: StakeManager = get_account(&mut context, pool.stake_manager).await;
+    assert_eq!(stake_manager.total_unstake_pending, 2 * LAMPORTS_PER_SOL);
+    assert_eq!(stake_manager.total_unstake_claimable, 2 * LAMPORTS_PER_SOL);
+    assert_eq!(
+        get_lamports(&mut context, pool.stake_pool).await,
+        rent + 2 * LAMPORTS_PER_SOL
+    );
+
+    // a later deposit is bonded, the unclaimed lamports stay in the pool
+    process(
+        &mut context,
+        &[pool.stake(&second.pubkey(), LAMPORTS_PER_SOL)],
+        &[&second],
+    )
+    .await
+    .unwrap();
+    run_era(&mut context, &pool, &mut bonded).await;
+    assert_eq!(
+        get_lamports(&mut context, pool.stake_pool).await,
+        rent + 2 * LAMPORTS_PER_SOL
+    );
+    let stake_manager: StakeManager = get_account(&mut context, pool.stake_manager).await;
+    assert_eq!(stake_manager.active, STAKE_AMOUNT + 2 * LAMPORTS_PER_SOL);
+    assert_eq!(stake_manager.total_unstake_claimable, 2 * LAMPORTS_PER_SOL);
+
+    process(
+        &mut context,
+        &[pool.withdraw(pool.unstake_account(&first.pubkey(), 0), first.pubkey())],
+        &[],
+    )
+    .await
+    .unwrap();
+    let stake_manager: StakeManager = get_account(&mut context, pool.stake_manager).await;
+    assert_eq!(stake_manager.total_unstake_pending, 0);
+    assert_eq!(stake_manager.total_unstake_claimable, 0);
+    assert_eq!(get_lamports(&mut context, pool.stake_pool).await, rent);
+}
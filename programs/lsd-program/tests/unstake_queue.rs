@@ -0,0 +1,170 @@
+mod common;
+
+use common::*;
+use lsd_program::{Errors, StakeManager, UnstakeAccount};
+use solana_sdk::{native_token::LAMPORTS_PER_SOL, signature::Keypair, signer::Signer};
+
+const STAKE_AMOUNT: u64 = 10 * LAMPORTS_PER_SOL;
+
+#[tokio::test]
+async fn unstakes_queue_in_order_and_are_reserved_by_the_next_era() {
+    let mut context = program_test().start_with_context().await;
+    let pool = setup_pool(&mut context).await;
+
+    let first = add_staker(&mut context, &pool, STAKE_AMOUNT + LAMPORTS_PER_SOL).await;
+    let second = add_staker(&mut context, &pool, STAKE_AMOUNT + LAMPORTS_PER_SOL).await;
+    for staker in [&first, &second] {
+        process(
+            &mut context,
+            &[pool.stake(&staker.pubkey(), STAKE_AMOUNT)],
+            &[staker],
+        )
+        .await
+        .unwrap();
+    }
+
+    let first_amount = 4 * LAMPORTS_PER_SOL;
+    let second_amount = 3 * LAMPORTS_PER_SOL;
+    let first_ticket = pool.unstake_account(&first.pubkey(), 0);
+    let second_ticket = pool.unstake_account(&second.pubkey(), 0);
+    process(
+        &mut context,
+        &[pool.unstake(&first.pubkey(), 0, first_amount)],
+        &[&first],
+    )
+    .await
+    .unwrap();
+    process(
+        &mut context,
+        &[pool.unstake(&second.pubkey(), 0, second_amount)],
+        &[&second],
+    )
+    .await
+    .unwrap();
+
+    let ticket: UnstakeAccount = get_account(&mut context, first_ticket).await;
+    assert_eq!(ticket.queue_position, 0);
+    assert_eq!(ticket.rent_payer, first.pubkey());
+    let ticket: UnstakeAccount = get_account(&mut context, second_ticket).await;
+    assert_eq!(ticket.queue_position, first_amount);
+    let stake_manager: StakeManager = get_account(&mut context, pool.stake_manager).await;
+    assert_eq!(
+        stake_manager.unstake_requested,
+        first_amount + second_amount
+    );
+    assert_eq!(stake_manager.unstake_fulfilled, 0);
+
+    // nothing is reserved before the era that funds the unstakes
+    assert_error(
+        process(
+            &mut context,
+            &[pool.withdraw(first_ticket, first.pubkey())],
+            &[],
+        )
+        .await,
+        Errors::UnstakeAccountNotClaimable,
+    );
+
+    // the era reserves the queue in the pool and bonds the rest
+    warp_to_next_epoch(&mut context).await;
+    let stake_account = Keypair::new();
+    let instructions = [
+        pool.era_new(),
+        pool.era_bond(&context.payer.pubkey(), &stake_account.pubkey()),
+        pool.era_update_active(&stake_account.pubkey()),
+        pool.era_update_rate(),
+    ];
+    process(&mut context, &instructions, &[&stake_account])
+        .await
+        .unwrap();
+    let stake_manager: StakeManager = get_account(&mut context, pool.stake_manager).await;
+    assert_eq!(
+        stake_manager.unstake_fulfilled,
+        first_amount + second_amount
+    );
+    assert_eq!(
+        get_lamports(&mut context, pool.stake_pool).await,
+        stake_manager.rent_exempt_for_pool_acc + first_amount + second_amount
+    );
+
+    // each ticket is paid in full and closed with its rent back to the payer
+    for _ in 1..StakeManager::DEFAULT_UNBONDING_DURATION {
+        warp_to_next_epoch(&mut context).await;
+    }
+    for (staker, ticket, amount) in [
+        (&second, second_ticket, second_amount),
+        (&first, first_ticket, first_amount),
+    ] {
+        let lamports_before = get_lamports(&mut context, staker.pubkey()).await;
+        process(&mut context, &[pool.withdraw(ticket, staker.pubkey())], &[])
+            .await
+            .unwrap();
+        assert!(get_lamports(&mut context, staker.pubkey()).await > lamports_before + amount);
+        assert!(!account_exists(&mut context, ticket).await);
+    }
+
+    let stake_manager: StakeManager = get_account(&mut context, pool.stake_manager).await;
+    assert_eq!(stake_manager.unstake_claimed, first_amount + second_amount);
+    assert_eq!(
+        get_lamports(&mut context, pool.stake_pool).await,
+        stake_manager.rent_exempt_for_pool_acc
+    );
+}
+
+#[tokio::test]
+async fn withdraw_batch_pays_every_ticket_and_refunds_each_rent_payer() {
+    let mut context = program_test().start_with_context().await;
+    let pool = setup_pool(&mut context).await;
+    let payer = context.payer.pubkey();
+
+    let staker = add_staker(&mut context, &pool, STAKE_AMOUNT + LAMPORTS_PER_SOL).await;
+    process(
+        &mut context,
+        &[pool.stake(&staker.pubkey(), STAKE_AMOUNT)],
+        &[&staker],
+    )
+    .await
+    .unwrap();
+
+    // the first ticket's rent is paid by someone else than the staker
+    let instructions = [
+        pool.unstake_to(&staker.pubkey(), 0, 2 * LAMPORTS_PER_SOL, None, &payer),
+        pool.unstake(&staker.pubkey(), 1, LAMPORTS_PER_SOL),
+    ];
+    process(&mut context, &instructions, &[&staker])
+        .await
+        .unwrap();
+    let tickets = [
+        (pool.unstake_account(&staker.pubkey(), 0), payer),
+        (pool.unstake_account(&staker.pubkey(), 1), staker.pubkey()),
+    ];
+    let ticket_rent = get_lamports(&mut context, tickets[0].0).await;
+
+    bond_era(&mut context, &pool).await;
+    for _ in 1..StakeManager::DEFAULT_UNBONDING_DURATION {
+        warp_to_next_epoch(&mut context).await;
+    }
+
+    let payer_before = get_lamports(&mut context, payer).await;
+    let staker_before = get_lamports(&mut context, staker.pubkey()).await;
+    process(
+        &mut context,
+        &[pool.withdraw_batch(staker.pubkey(), &tickets)],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    // the transaction fee is the only cost of the payer
+    assert_eq!(
+        get_lamports(&mut context, payer).await,
+        payer_before - 5000 + ticket_rent
+    );
+    assert_eq!(
+        get_lamports(&mut context, staker.pubkey()).await,
+        staker_before + 3 * LAMPORTS_PER_SOL + ticket_rent
+    );
+    for (ticket, _) in tickets {
+        assert!(!account_exists(&mut context, ticket).await);
+    }
+}